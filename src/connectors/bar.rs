@@ -0,0 +1,111 @@
+/*
+  Bar connector
+  Name: bar
+  Controls: waybar, polybar and eww colors
+
+  Config options:
+  - Waybar: Option<Palette> - Colors for `univeme-colors.css`, as `@define-color`. None = do not change
+  - Polybar: Option<Palette> - Colors for the `[colors]` section of `univeme-colors.ini`. None = do not change
+  - Eww: Option<Palette> - Colors for `univeme-colors.scss`, as SCSS variables. None = do not change
+  NOTE: univeme only owns the colors files, next to each bar's own config. The bar's style has to include
+  them once, see the README. Running bars are reloaded afterwards.
+*/
+
+use std::{error::Error, path::PathBuf};
+
+use crate::{
+    edit,
+    palette::{Color, Palette},
+    process, xdg,
+};
+
+use super::Connector;
+
+custom_error::custom_error! {pub BarError
+    MissingHome = "could not find the home folder"
+}
+
+pub struct Bar {
+    pub waybar: Option<Palette>,
+    pub polybar: Option<Palette>,
+    pub eww: Option<Palette>,
+}
+
+impl Bar {
+    fn config_home() -> Result<PathBuf, BarError> {
+        xdg::config_home().ok_or(BarError::MissingHome)
+    }
+    fn generate(palette: &Palette, header: &str, line: impl Fn(&str, Color) -> String) -> String {
+        let mut contents = format!("{}\n", header);
+        for (name, color) in palette.named_colors() {
+            contents.push_str(&line(&name, color));
+            contents.push('\n');
+        }
+        contents
+    }
+    pub fn waybar_css(palette: &Palette) -> String {
+        Bar::generate(
+            palette,
+            "/* Generated by univeme from the preset palette */",
+            |name, color| format!("@define-color {} {};", name, color),
+        )
+    }
+    pub fn polybar_ini(palette: &Palette) -> String {
+        Bar::generate(
+            palette,
+            "; Generated by univeme from the preset palette\n[colors]",
+            |name, color| format!("{} = {}", name, color),
+        )
+    }
+    pub fn eww_scss(palette: &Palette) -> String {
+        Bar::generate(
+            palette,
+            "// Generated by univeme from the preset palette",
+            |name, color| format!("${}: {};", name, color),
+        )
+    }
+}
+
+#[async_trait::async_trait]
+impl Connector for Bar {
+    fn new() -> Result<Self, Box<dyn Error + 'static>> {
+        Ok(Self {
+            waybar: None,
+            polybar: None,
+            eww: None,
+        })
+    }
+    fn verify(&self) -> Result<(), Box<dyn Error + 'static>> {
+        let _ = Bar::config_home()?;
+        Ok(())
+    }
+    async fn apply(&self) -> Result<(), Box<dyn Error + 'static>> {
+        let config_home = Bar::config_home()?;
+        if let Some(palette) = &self.waybar {
+            edit::file(
+                &config_home.join("waybar").join("univeme-colors.css"),
+                |_| Bar::waybar_css(palette),
+            )?;
+            // SIGUSR2 makes waybar reload its config and style
+            process::run("pkill", &["-USR2", "-x", "waybar"]);
+        }
+        if let Some(palette) = &self.polybar {
+            edit::file(
+                &config_home.join("polybar").join("univeme-colors.ini"),
+                |_| Bar::polybar_ini(palette),
+            )?;
+            if process::is_running("polybar") {
+                process::run("polybar-msg", &["cmd", "restart"]);
+            }
+        }
+        if let Some(palette) = &self.eww {
+            edit::file(&config_home.join("eww").join("univeme-colors.scss"), |_| {
+                Bar::eww_scss(palette)
+            })?;
+            if process::is_running("eww") {
+                process::run("eww", &["reload"]);
+            }
+        }
+        Ok(())
+    }
+}
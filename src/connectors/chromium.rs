@@ -0,0 +1,452 @@
+/*
+  Chromium connector
+  Name: chromium
+  Controls: Chrome, Chromium, Brave and Edge color mode and theme color
+
+  Config options:
+  - Profiles: Vec<ChromiumProfile> - Browser profiles to change, from each browser's `Local State`.
+  - Color scheme: Option<ColorScheme> - System, light or dark. None = do not change
+  - User color: Option<Color> - The "Customize Chrome" theme color. None = do not change
+  - Theme palette: Option<Palette> - Colors for a generated theme extension. None = do not change
+  NOTE: The browser keeps its `Preferences` in memory and writes them back when it exits, so
+  a running browser is an error in `verify`, before any connector has written anything.
+  NOTE 2: Browsers do not allow installing extensions from outside, so the generated theme has to be
+  loaded once from chrome://extensions ("Load unpacked"). After that, applying again updates it.
+*/
+
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use serde_json::{json, Value};
+
+use crate::{
+    palette::{Color, Palette},
+    process,
+};
+
+use super::Connector;
+
+custom_error::custom_error! {pub ChromiumError
+    NoProfiles = "could not find any Chromium-based browser profiles",
+    UnknownBrowser{name: String} = "unknown browser (expected chrome, chromium, brave or edge): {name}",
+    ProfileNotFound{name: String} = "browser profile not found: {name}",
+    InvalidColorScheme{value: String} = "invalid color scheme (expected system, light or dark): {value}",
+    MalformedPreferences{path: String} = "browser Preferences are not valid: {path}",
+    BrowserRunning{name: String} = "{name} is running, close it first so it does not overwrite the changes",
+    MissingDataDir = "could not find the local data folder"
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorScheme {
+    System,
+    Light,
+    Dark,
+}
+
+impl FromStr for ColorScheme {
+    type Err = ChromiumError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "system" => Ok(ColorScheme::System),
+            "light" => Ok(ColorScheme::Light),
+            "dark" => Ok(ColorScheme::Dark),
+            _ => Err(ChromiumError::InvalidColorScheme {
+                value: s.to_string(),
+            }),
+        }
+    }
+}
+
+struct Browser {
+    name: &'static str,
+    // Relative to the local app data folder on Windows, and the config folder elsewhere
+    user_data: &'static [&'static str],
+    processes: &'static [&'static str],
+}
+
+#[cfg(windows)]
+const BROWSERS: [Browser; 4] = [
+    Browser {
+        name: "chrome",
+        user_data: &["Google", "Chrome", "User Data"],
+        processes: &["chrome"],
+    },
+    Browser {
+        name: "chromium",
+        user_data: &["Chromium", "User Data"],
+        processes: &["chrome"],
+    },
+    Browser {
+        name: "brave",
+        user_data: &["BraveSoftware", "Brave-Browser", "User Data"],
+        processes: &["brave"],
+    },
+    Browser {
+        name: "edge",
+        user_data: &["Microsoft", "Edge", "User Data"],
+        processes: &["msedge"],
+    },
+];
+
+#[cfg(target_os = "macos")]
+const BROWSERS: [Browser; 4] = [
+    Browser {
+        name: "chrome",
+        user_data: &["Google", "Chrome"],
+        processes: &["Google Chrome"],
+    },
+    Browser {
+        name: "chromium",
+        user_data: &["Chromium"],
+        processes: &["Chromium"],
+    },
+    Browser {
+        name: "brave",
+        user_data: &["BraveSoftware", "Brave-Browser"],
+        processes: &["Brave Browser"],
+    },
+    Browser {
+        name: "edge",
+        user_data: &["Microsoft Edge"],
+        processes: &["Microsoft Edge"],
+    },
+];
+
+#[cfg(all(unix, not(target_os = "macos")))]
+const BROWSERS: [Browser; 4] = [
+    Browser {
+        name: "chrome",
+        user_data: &["google-chrome"],
+        processes: &["chrome"],
+    },
+    Browser {
+        name: "chromium",
+        user_data: &["chromium"],
+        processes: &["chromium", "chromium-browser"],
+    },
+    Browser {
+        name: "brave",
+        user_data: &["BraveSoftware", "Brave-Browser"],
+        processes: &["brave"],
+    },
+    Browser {
+        name: "edge",
+        user_data: &["microsoft-edge"],
+        processes: &["msedge"],
+    },
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChromiumProfile {
+    pub browser: String,
+    // The name shown in the browser's profile menu
+    pub name: String,
+    pub path: PathBuf,
+}
+
+pub struct Chromium {
+    pub profiles: Vec<ChromiumProfile>,
+    pub color_scheme: Option<ColorScheme>,
+    pub user_color: Option<Color>,
+    pub theme_palette: Option<Palette>,
+}
+
+// Chromium stores colors as signed 32-bit ARGB
+fn argb(color: &Color) -> i32 {
+    (0xFF000000u32 | (u32::from(color.r) << 16) | (u32::from(color.g) << 8) | u32::from(color.b))
+        as i32
+}
+
+fn set_path(root: &mut Value, path: &[&str], value: Value) {
+    let mut current = root;
+    for key in path {
+        if !current.is_object() {
+            *current = json!({});
+        }
+        current = current
+            .as_object_mut()
+            .unwrap()
+            .entry(key.to_string())
+            .or_insert(Value::Null);
+    }
+    *current = value;
+}
+
+impl Chromium {
+    fn base_dir() -> Option<PathBuf> {
+        if cfg!(windows) {
+            dirs::data_local_dir()
+        } else {
+            dirs::config_dir()
+        }
+    }
+    /// Profile folders and names from the contents of a browser's `Local State`.
+    pub fn parse_local_state(contents: &str) -> Vec<(String, String)> {
+        let Ok(local_state) = serde_json::from_str::<Value>(contents) else {
+            return vec![];
+        };
+        let mut profiles = local_state
+            .pointer("/profile/info_cache")
+            .and_then(Value::as_object)
+            .into_iter()
+            .flatten()
+            .map(|(dir, info)| {
+                let name = info.get("name").and_then(Value::as_str).unwrap_or(dir);
+                (dir.clone(), name.to_string())
+            })
+            .collect::<Vec<_>>();
+        profiles.sort();
+        profiles
+    }
+    pub fn get_profiles() -> Vec<ChromiumProfile> {
+        let Some(base) = Chromium::base_dir() else {
+            return vec![];
+        };
+        let mut profiles = vec![];
+        for browser in &BROWSERS {
+            let user_data = browser
+                .user_data
+                .iter()
+                .fold(base.clone(), |path, part| path.join(part));
+            let Ok(local_state) = fs::read_to_string(user_data.join("Local State")) else {
+                continue;
+            };
+            for (dir, name) in Chromium::parse_local_state(&local_state) {
+                let path = user_data.join(dir);
+                if path.join("Preferences").exists() {
+                    profiles.push(ChromiumProfile {
+                        browser: browser.name.to_string(),
+                        name,
+                        path,
+                    });
+                }
+            }
+        }
+        profiles
+    }
+    /// The profiles of `browser` (all browsers when None) with the given names, which can be the
+    /// name in the profile menu or the folder name. All profiles when there are no names.
+    pub fn select(
+        profiles: Vec<ChromiumProfile>,
+        browser: &Option<String>,
+        names: &Option<Vec<String>>,
+    ) -> Result<Vec<ChromiumProfile>, ChromiumError> {
+        if let Some(browser) = browser {
+            if !BROWSERS.iter().any(|known| known.name == browser) {
+                return Err(ChromiumError::UnknownBrowser {
+                    name: browser.clone(),
+                });
+            }
+        }
+        let profiles = profiles
+            .into_iter()
+            .filter(|profile| {
+                browser
+                    .as_ref()
+                    .is_none_or(|browser| &profile.browser == browser)
+            })
+            .collect::<Vec<_>>();
+        let Some(names) = names else {
+            return Ok(profiles);
+        };
+        names
+            .iter()
+            .map(|name| {
+                profiles
+                    .iter()
+                    .find(|profile| {
+                        profile.name.eq_ignore_ascii_case(name)
+                            || profile
+                                .path
+                                .file_name()
+                                .is_some_and(|dir| dir.eq_ignore_ascii_case(name))
+                    })
+                    .cloned()
+                    .ok_or_else(|| ChromiumError::ProfileNotFound { name: name.clone() })
+            })
+            .collect()
+    }
+    /// Sets the color scheme and theme color in the contents of a profile's `Preferences`.
+    pub fn set_preferences(
+        contents: &str,
+        color_scheme: Option<ColorScheme>,
+        user_color: Option<&Color>,
+    ) -> Option<String> {
+        let mut preferences = serde_json::from_str::<Value>(contents).ok()?;
+        if !preferences.is_object() {
+            return None;
+        }
+        if let Some(color_scheme) = color_scheme {
+            let value = json!(match color_scheme {
+                ColorScheme::System => 0,
+                ColorScheme::Light => 1,
+                ColorScheme::Dark => 2,
+            });
+            // Newer versions moved the setting to `color_scheme2`
+            set_path(
+                &mut preferences,
+                &["browser", "theme", "color_scheme"],
+                value.clone(),
+            );
+            set_path(
+                &mut preferences,
+                &["browser", "theme", "color_scheme2"],
+                value,
+            );
+        }
+        if let Some(user_color) = user_color {
+            set_path(
+                &mut preferences,
+                &["browser", "theme", "user_color"],
+                json!(argb(user_color)),
+            );
+            set_path(
+                &mut preferences,
+                &["browser", "theme", "user_color2"],
+                json!(argb(user_color)),
+            );
+            // A grayscale theme hides the color
+            set_path(
+                &mut preferences,
+                &["browser", "theme", "is_grayscale2"],
+                json!(false),
+            );
+        }
+        Some(preferences.to_string())
+    }
+    /// The `manifest.json` of a theme extension with the palette's colors.
+    pub fn theme_manifest(palette: &Palette) -> Value {
+        let rgb = |color: Color| json!([color.r, color.g, color.b]);
+        let surface = palette.surface();
+        json!({
+            "manifest_version": 3,
+            "name": "univeme palette",
+            "version": "1.0",
+            "description": "Generated by univeme from the preset palette",
+            "theme": {
+                "colors": {
+                    "frame": rgb(palette.background),
+                    "frame_inactive": rgb(palette.background),
+                    "toolbar": rgb(surface),
+                    "tab_text": rgb(palette.foreground),
+                    "tab_background_text": rgb(palette.foreground),
+                    "bookmark_text": rgb(palette.foreground),
+                    "toolbar_text": rgb(palette.foreground),
+                    "toolbar_button_icon": rgb(palette.foreground),
+                    "omnibox_background": rgb(palette.background),
+                    "omnibox_text": rgb(palette.foreground),
+                    "ntp_background": rgb(palette.background),
+                    "ntp_text": rgb(palette.foreground),
+                    "ntp_link": rgb(palette.accent),
+                    "button_background": rgb(palette.accent),
+                }
+            }
+        })
+    }
+    /// Where the generated theme extension is written.
+    pub fn theme_dir() -> Result<PathBuf, ChromiumError> {
+        Ok(dirs::data_dir()
+            .ok_or(ChromiumError::MissingDataDir)?
+            .join("univeme")
+            .join("chromium-theme"))
+    }
+    // Whether the profile has the unpacked extension at `dir` loaded
+    fn has_extension(profile: &Path, dir: &Path) -> bool {
+        ["Preferences", "Secure Preferences"].iter().any(|file| {
+            let Ok(contents) = fs::read_to_string(profile.join(file)) else {
+                return false;
+            };
+            let Ok(preferences) = serde_json::from_str::<Value>(&contents) else {
+                return false;
+            };
+            preferences
+                .pointer("/extensions/settings")
+                .and_then(Value::as_object)
+                .into_iter()
+                .flatten()
+                .filter_map(|(_, extension)| extension.get("path").and_then(Value::as_str))
+                .any(|path| Path::new(path) == dir)
+        })
+    }
+    /// Profiles that have not loaded the generated theme yet, which has to be done by hand.
+    pub fn profiles_without_theme(&self) -> Result<Vec<&ChromiumProfile>, ChromiumError> {
+        if self.theme_palette.is_none() {
+            return Ok(vec![]);
+        }
+        let dir = Chromium::theme_dir()?;
+        Ok(self
+            .profiles
+            .iter()
+            .filter(|profile| !Chromium::has_extension(&profile.path, &dir))
+            .collect())
+    }
+    fn running_browser(&self) -> Option<&'static str> {
+        BROWSERS
+            .iter()
+            .filter(|browser| {
+                self.profiles
+                    .iter()
+                    .any(|profile| profile.browser == browser.name)
+            })
+            .find(|browser| {
+                browser
+                    .processes
+                    .iter()
+                    .any(|name| process::is_running(name))
+            })
+            .map(|browser| browser.name)
+    }
+}
+
+#[async_trait::async_trait]
+impl Connector for Chromium {
+    fn new() -> Result<Self, Box<dyn Error + 'static>> {
+        Ok(Self {
+            profiles: Chromium::get_profiles(),
+            color_scheme: None,
+            user_color: None,
+            theme_palette: None,
+        })
+    }
+    fn verify(&self) -> Result<(), Box<dyn Error + 'static>> {
+        if self.profiles.is_empty() {
+            return Err(ChromiumError::NoProfiles.into());
+        }
+        if self.color_scheme.is_some() || self.user_color.is_some() {
+            if let Some(name) = self.running_browser() {
+                return Err(ChromiumError::BrowserRunning {
+                    name: name.to_string(),
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+    async fn apply(&self) -> Result<(), Box<dyn Error + 'static>> {
+        if self.color_scheme.is_some() || self.user_color.is_some() {
+            for profile in &self.profiles {
+                let path = profile.path.join("Preferences");
+                let contents = Chromium::set_preferences(
+                    &fs::read_to_string(&path)?,
+                    self.color_scheme,
+                    self.user_color.as_ref(),
+                )
+                .ok_or_else(|| ChromiumError::MalformedPreferences {
+                    path: path.to_string_lossy().to_string(),
+                })?;
+                fs::write(&path, contents)?;
+            }
+        }
+        if let Some(palette) = &self.theme_palette {
+            let dir = Chromium::theme_dir()?;
+            fs::create_dir_all(&dir)?;
+            let manifest = serde_json::to_string_pretty(&Chromium::theme_manifest(palette))?;
+            fs::write(dir.join("manifest.json"), manifest)?;
+        }
+        Ok(())
+    }
+}
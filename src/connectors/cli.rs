@@ -0,0 +1,512 @@
+/*
+  CLI connector
+  Name: cli
+  Controls: bat and delta syntax themes, starship palette, btop theme, fzf colors
+
+  Config options:
+  - Bat theme: Option<String> - Name from `bat --list-themes`, set in bat's config file. None = do not change
+  - Delta syntax theme: Option<String> - Same names as bat, set in the global git config. None = do not change
+  - Starship palette: Option<Palette> - Colors for a `univeme` palette in starship.toml, which gets selected. None = do not change
+  - Btop theme: Option<String> - Name of an installed btop theme. None = do not change
+  - Btop palette: Option<Palette> - Colors for a generated `univeme` btop theme, which gets selected. None = do not change
+  - Fzf palette: Option<Palette> - Colors for `FZF_DEFAULT_OPTS`, in an env file for the shell to source. None = do not change
+  NOTE: Every file is edited in place, so the rest of each tool's settings and comments stay as they were.
+  starship.toml is edited as TOML, so the palette can already exist as a table, an inline table or dotted keys.
+  NOTE 2: btop writes its config when it exits, so it has to be closed while applying.
+*/
+
+use std::{error::Error, fs, path::PathBuf};
+
+use toml_edit::{DocumentMut, InlineTable, Item, Table};
+
+use crate::{
+    edit::{self, block, ini},
+    palette::{Color, Palette},
+    process, xdg,
+};
+
+use super::Connector;
+
+custom_error::custom_error! {pub CliError
+    MissingHome = "could not find the home folder",
+    BatNotFound = "could not find bat to check theme names, is it installed?",
+    ThemeNotFound{tool: String, name: String} = "{tool} theme not found: {name}",
+    GitConfigFailed = "could not set delta.syntax-theme with `git config --global`",
+    InvalidStarshipConfig{reason: String} = "could not edit starship.toml: {reason}",
+    BtopRunning = "btop is running, close it first so it does not overwrite its config"
+}
+
+/// Name of the palette and themes generated from the preset palette.
+pub const PALETTE_NAME: &str = "univeme";
+
+/// Starship's names for the 16 terminal colors. Palette entries with these names replace the
+/// built-in colors, so existing prompt styles pick them up.
+const ANSI_NAMES: [&str; 16] = [
+    "black",
+    "red",
+    "green",
+    "yellow",
+    "blue",
+    "purple",
+    "cyan",
+    "white",
+    "bright-black",
+    "bright-red",
+    "bright-green",
+    "bright-yellow",
+    "bright-blue",
+    "bright-purple",
+    "bright-cyan",
+    "bright-white",
+];
+
+pub struct Cli {
+    pub bat_theme: Option<String>,
+    pub delta_syntax_theme: Option<String>,
+    pub starship_palette: Option<Palette>,
+    pub btop_theme: Option<String>,
+    pub btop_palette: Option<Palette>,
+    pub fzf_palette: Option<Palette>,
+}
+
+impl Cli {
+    fn config_home() -> Result<PathBuf, CliError> {
+        xdg::config_home().ok_or(CliError::MissingHome)
+    }
+    fn bat_config() -> Result<PathBuf, CliError> {
+        if let Some(path) = std::env::var_os("BAT_CONFIG_PATH") {
+            return Ok(PathBuf::from(path));
+        }
+        #[cfg(windows)]
+        let config = dirs::config_dir().ok_or(CliError::MissingHome)?;
+        #[cfg(not(windows))]
+        let config = Cli::config_home()?;
+        Ok(config.join("bat").join("config"))
+    }
+    fn starship_config() -> Result<PathBuf, CliError> {
+        if let Some(path) = std::env::var_os("STARSHIP_CONFIG") {
+            return Ok(PathBuf::from(path));
+        }
+        Ok(Cli::config_home()?.join("starship.toml"))
+    }
+    fn btop_dir() -> Result<PathBuf, CliError> {
+        Ok(Cli::config_home()?.join("btop"))
+    }
+    /// The env file that sets `FZF_DEFAULT_OPTS`, for the shell's rc file to source.
+    pub fn fzf_env_file() -> Result<PathBuf, CliError> {
+        Ok(dirs::config_dir()
+            .ok_or(CliError::MissingHome)?
+            .join("univeme")
+            .join("fzf.sh"))
+    }
+    /// Themes bat (and delta, which bundles the same ones) knows about.
+    pub fn get_bat_themes() -> Option<Vec<String>> {
+        // Debian and Ubuntu install bat as `batcat`
+        let output = process::output("bat", &["--list-themes", "--color=never"])
+            .or_else(|| process::output("batcat", &["--list-themes", "--color=never"]))?;
+        Some(
+            output
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(str::to_string)
+                .collect(),
+        )
+    }
+    pub fn get_btop_themes() -> Vec<String> {
+        let mut dirs = vec![];
+        if let Ok(btop_dir) = Cli::btop_dir() {
+            dirs.push(btop_dir.join("themes"));
+        }
+        dirs.extend(
+            xdg::data_dirs()
+                .into_iter()
+                .map(|data| data.join("btop").join("themes")),
+        );
+        let mut themes = vec!["Default".to_string(), "TTY".to_string()];
+        for dir in dirs {
+            let Ok(entries) = fs::read_dir(dir) else {
+                continue;
+            };
+            themes.extend(entries.flatten().filter_map(|entry| {
+                let path = entry.path();
+                if path.extension()? != "theme" {
+                    return None;
+                }
+                Some(path.file_stem()?.to_string_lossy().to_string())
+            }));
+        }
+        themes.sort();
+        themes.dedup();
+        themes
+    }
+    /// Sets `--flag=value` in the contents of a file of command line flags, like bat's config.
+    /// Comments and other flags are kept.
+    pub fn set_flag(contents: &str, flag: &str, value: &str) -> String {
+        let line = format!("{}=\"{}\"", flag, value);
+        let mut lines = contents.lines().map(str::to_string).collect::<Vec<_>>();
+        let existing = lines.iter().position(|existing| {
+            let existing = existing.trim();
+            existing == flag
+                || existing.starts_with(&format!("{}=", flag))
+                || existing.starts_with(&format!("{} ", flag))
+        });
+        match existing {
+            Some(i) => lines[i] = line,
+            None => lines.push(line),
+        }
+        let mut output = lines.join("\n");
+        output.push('\n');
+        output
+    }
+    /// Entries of the `univeme` starship palette.
+    pub fn starship_colors(palette: &Palette) -> Vec<(String, Color)> {
+        let mut colors = vec![
+            ("background".to_string(), palette.background),
+            ("foreground".to_string(), palette.foreground),
+            ("accent".to_string(), palette.accent),
+            ("accent_foreground".to_string(), palette.accent_foreground()),
+            ("surface".to_string(), palette.surface()),
+        ];
+        for (name, color) in ANSI_NAMES.iter().zip(palette.colors.iter().flatten()) {
+            colors.push((name.to_string(), *color));
+        }
+        colors
+    }
+    /// Sets the `univeme` palette and selects it, in the contents of starship.toml.
+    pub fn set_starship_palette(contents: &str, palette: &Palette) -> Result<String, CliError> {
+        let invalid = |reason: &str| CliError::InvalidStarshipConfig {
+            reason: reason.to_string(),
+        };
+        let mut document = contents
+            .parse::<DocumentMut>()
+            .map_err(|error| invalid(error.message()))?;
+        document["palette"] = toml_edit::value(PALETTE_NAME);
+        // Only `[palettes.univeme]` gets a header when there are no palettes yet
+        let palettes = document.entry("palettes").or_insert_with(|| {
+            let mut palettes = Table::new();
+            palettes.set_implicit(true);
+            Item::Table(palettes)
+        });
+        // Inline tables can only hold more inline tables
+        let empty = match palettes.is_inline_table() {
+            true => Item::Value(InlineTable::new().into()),
+            false => toml_edit::table(),
+        };
+        let colors = palettes
+            .as_table_like_mut()
+            .ok_or_else(|| invalid("`palettes` is not a table"))?
+            .entry(PALETTE_NAME)
+            .or_insert(empty)
+            .as_table_like_mut()
+            .ok_or_else(|| invalid("`palettes.univeme` is not a table"))?;
+        for (name, color) in Cli::starship_colors(palette) {
+            colors.insert(&name, toml_edit::value(color.to_string()));
+        }
+        Ok(document.to_string())
+    }
+    pub fn btop_theme_file(palette: &Palette) -> String {
+        let background = palette.background;
+        let foreground = palette.foreground;
+        let accent = palette.accent;
+        let surface = palette.surface();
+        let muted = foreground.mix(&background, 0.5);
+        let color = |index: usize, fallback: Color| {
+            palette.role(&format!("color{}", index)).unwrap_or(fallback)
+        };
+        let green = color(2, accent);
+        let yellow = color(3, accent);
+        let red = color(1, accent);
+        let blue = color(4, accent);
+        let magenta = color(5, accent);
+        let cyan = color(6, accent);
+        let entries = [
+            ("main_bg", background),
+            ("main_fg", foreground),
+            ("title", foreground),
+            ("hi_fg", accent),
+            ("selected_bg", surface),
+            ("selected_fg", accent),
+            ("inactive_fg", muted),
+            ("graph_text", foreground),
+            ("meter_bg", surface),
+            ("proc_misc", accent),
+            ("cpu_box", accent),
+            ("mem_box", accent),
+            ("net_box", accent),
+            ("proc_box", accent),
+            ("div_line", surface.mix(&foreground, 0.2)),
+            ("temp_start", green),
+            ("temp_mid", yellow),
+            ("temp_end", red),
+            ("cpu_start", green),
+            ("cpu_mid", yellow),
+            ("cpu_end", red),
+            ("free_start", green),
+            ("free_mid", green),
+            ("free_end", green),
+            ("cached_start", blue),
+            ("cached_mid", blue),
+            ("cached_end", blue),
+            ("available_start", cyan),
+            ("available_mid", cyan),
+            ("available_end", cyan),
+            ("used_start", red),
+            ("used_mid", red),
+            ("used_end", red),
+            ("download_start", blue),
+            ("download_mid", magenta),
+            ("download_end", accent),
+            ("upload_start", green),
+            ("upload_mid", yellow),
+            ("upload_end", red),
+            ("process_start", green),
+            ("process_mid", yellow),
+            ("process_end", red),
+        ];
+        let mut theme = String::from("# Generated by univeme from the preset palette\n");
+        for (key, color) in entries {
+            theme.push_str(&format!("theme[{}]=\"{}\"\n", key, color));
+        }
+        theme
+    }
+    /// The `--color` option for fzf.
+    pub fn fzf_colors(palette: &Palette) -> String {
+        let background = palette.background;
+        let foreground = palette.foreground;
+        let accent = palette.accent;
+        let surface = palette.surface();
+        [
+            ("fg", foreground),
+            ("bg", background),
+            ("hl", accent),
+            ("fg+", foreground),
+            ("bg+", surface),
+            ("hl+", accent),
+            ("info", foreground.mix(&background, 0.4)),
+            ("prompt", accent),
+            ("pointer", accent),
+            ("marker", accent),
+            ("spinner", accent),
+            ("header", accent),
+            ("border", surface.mix(&foreground, 0.2)),
+            ("gutter", background),
+        ]
+        .iter()
+        .map(|(name, color)| format!("{}:{}", name, color))
+        .collect::<Vec<_>>()
+        .join(",")
+    }
+    fn verify_theme(tool: &str, name: &str, available: &[String]) -> Result<(), CliError> {
+        if available.iter().any(|theme| theme == name) {
+            Ok(())
+        } else {
+            Err(CliError::ThemeNotFound {
+                tool: tool.to_string(),
+                name: name.to_string(),
+            })
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Connector for Cli {
+    fn new() -> Result<Self, Box<dyn Error + 'static>> {
+        Ok(Self {
+            bat_theme: None,
+            delta_syntax_theme: None,
+            starship_palette: None,
+            btop_theme: None,
+            btop_palette: None,
+            fzf_palette: None,
+        })
+    }
+    fn verify(&self) -> Result<(), Box<dyn Error + 'static>> {
+        let _ = Cli::config_home()?;
+        if self.bat_theme.is_some() || self.delta_syntax_theme.is_some() {
+            let bat_themes = Cli::get_bat_themes().ok_or(CliError::BatNotFound)?;
+            if let Some(bat_theme) = &self.bat_theme {
+                Cli::verify_theme("bat", bat_theme, &bat_themes)?;
+            }
+            if let Some(delta_syntax_theme) = &self.delta_syntax_theme {
+                Cli::verify_theme("delta", delta_syntax_theme, &bat_themes)?;
+            }
+        }
+        if let (Some(btop_theme), None) = (&self.btop_theme, &self.btop_palette) {
+            Cli::verify_theme("btop", btop_theme, &Cli::get_btop_themes())?;
+        }
+        if (self.btop_theme.is_some() || self.btop_palette.is_some()) && process::is_running("btop")
+        {
+            return Err(CliError::BtopRunning.into());
+        }
+        Ok(())
+    }
+    async fn apply(&self) -> Result<(), Box<dyn Error + 'static>> {
+        if let Some(bat_theme) = &self.bat_theme {
+            edit::file(&Cli::bat_config()?, |contents| {
+                Cli::set_flag(contents, "--theme", bat_theme)
+            })?;
+        }
+        if let Some(delta_syntax_theme) = &self.delta_syntax_theme {
+            if !process::run(
+                "git",
+                &[
+                    "config",
+                    "--global",
+                    "delta.syntax-theme",
+                    delta_syntax_theme,
+                ],
+            ) {
+                return Err(CliError::GitConfigFailed.into());
+            }
+        }
+        if let Some(palette) = &self.starship_palette {
+            let starship_config = Cli::starship_config()?;
+            let contents = Cli::set_starship_palette(&edit::read(&starship_config)?, palette)?;
+            edit::file(&starship_config, |_| contents)?;
+        }
+        let btop_theme = match &self.btop_palette {
+            Some(palette) => {
+                edit::file(
+                    &Cli::btop_dir()?
+                        .join("themes")
+                        .join(format!("{}.theme", PALETTE_NAME)),
+                    |_| Cli::btop_theme_file(palette),
+                )?;
+                Some(PALETTE_NAME.to_string())
+            }
+            None => self.btop_theme.clone(),
+        };
+        if let Some(btop_theme) = btop_theme {
+            edit::file(&Cli::btop_dir()?.join("btop.conf"), |contents| {
+                ini::set(
+                    contents,
+                    None,
+                    "color_theme",
+                    &format!("\"{}\"", btop_theme),
+                )
+            })?;
+        }
+        if let Some(palette) = &self.fzf_palette {
+            edit::file(&Cli::fzf_env_file()?, |contents| {
+                block::replace(
+                    contents,
+                    &block::HASH,
+                    "fzf",
+                    &format!(
+                        "export FZF_DEFAULT_OPTS=\"$FZF_DEFAULT_OPTS --color={}\"",
+                        Cli::fzf_colors(palette)
+                    ),
+                )
+            })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn palette() -> Palette {
+        toml::from_str(
+            r##"
+            background = "#000000"
+            foreground = "#ffffff"
+            accent = "#ff0080"
+            colors = ["#000000", "#ff0000"]
+            "##,
+        )
+        .unwrap()
+    }
+
+    fn starship(contents: &str) -> toml::Table {
+        let contents = Cli::set_starship_palette(contents, &palette()).unwrap();
+        toml::from_str(&contents).unwrap()
+    }
+
+    #[test]
+    fn starship_palette_in_a_new_file() {
+        let contents = Cli::set_starship_palette("", &palette()).unwrap();
+        assert!(contents.starts_with("palette = \"univeme\"\n"));
+        assert!(contents.contains("\n[palettes.univeme]\n"));
+        assert!(!contents.contains("[palettes]"));
+        let config = starship("");
+        assert_eq!(
+            config["palettes"]["univeme"]["accent"].as_str(),
+            Some("#ff0080")
+        );
+        assert_eq!(
+            config["palettes"]["univeme"]["red"].as_str(),
+            Some("#ff0000")
+        );
+    }
+
+    #[test]
+    fn starship_palette_keeps_the_rest() {
+        let contents = r##"# prompt
+format = """
+$directory
+[palette = "x"](red)
+"""
+palette = "other"
+
+[palettes.other]
+red = "#110000"
+
+[palettes.univeme]
+accent = '#123456' # old
+extra = "#654321"
+
+[character]
+success_symbol = "[>](bold green)"
+"##;
+        let output = Cli::set_starship_palette(contents, &palette()).unwrap();
+        assert!(output.starts_with(
+            "# prompt\nformat = \"\"\"\n$directory\n[palette = \"x\"](red)\n\"\"\"\n"
+        ));
+        let config = starship(contents);
+        assert_eq!(config["palette"].as_str(), Some("univeme"));
+        assert_eq!(config["palettes"]["other"]["red"].as_str(), Some("#110000"));
+        assert_eq!(
+            config["palettes"]["univeme"]["accent"].as_str(),
+            Some("#ff0080")
+        );
+        assert_eq!(
+            config["palettes"]["univeme"]["extra"].as_str(),
+            Some("#654321")
+        );
+        assert_eq!(
+            config["character"]["success_symbol"].as_str(),
+            Some("[>](bold green)")
+        );
+    }
+
+    #[test]
+    fn starship_palette_in_inline_and_dotted_tables() {
+        let config = starship("palettes = { univeme = { accent = \"#123456\" }, other = {} }\n");
+        assert_eq!(
+            config["palettes"]["univeme"]["accent"].as_str(),
+            Some("#ff0080")
+        );
+        assert!(config["palettes"]["other"].is_table());
+        let config = starship("palettes.other.red = \"#110000\"\n");
+        assert_eq!(config["palettes"]["other"]["red"].as_str(), Some("#110000"));
+        assert_eq!(
+            config["palettes"]["univeme"]["red"].as_str(),
+            Some("#ff0000")
+        );
+    }
+
+    #[test]
+    fn starship_palette_errors() {
+        assert!(matches!(
+            Cli::set_starship_palette("palettes = 1\n", &palette()),
+            Err(CliError::InvalidStarshipConfig { .. })
+        ));
+        assert!(matches!(
+            Cli::set_starship_palette("[broken\n", &palette()),
+            Err(CliError::InvalidStarshipConfig { .. })
+        ));
+    }
+}
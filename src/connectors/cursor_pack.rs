@@ -0,0 +1,467 @@
+/*
+  Cursor pack support
+  Used by: windows
+
+  Reads the `install.inf` that Windows cursor packs ship with, so a downloaded pack can be
+  installed without right-clicking the INF. Nothing here touches the registry, so it works the
+  same on every platform.
+*/
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+custom_error::custom_error! {pub CursorPackError
+    MissingInf = "cursor pack has no .inf file",
+    MissingScheme = "cursor pack .inf does not define a cursor scheme",
+    MissingFile{name: String} = "cursor pack is missing a file: {name}",
+    UnsafeName{name: String} = "cursor pack names a file outside its folder: {name}"
+}
+
+/// The order cursors appear in, both in a scheme's registry value and as values under
+/// `Control Panel\Cursors`.
+pub const CURSOR_ORDER: [&str; 15] = [
+    "Arrow",
+    "Help",
+    "AppStarting",
+    "Wait",
+    "Crosshair",
+    "IBeam",
+    "NWPen",
+    "No",
+    "SizeNS",
+    "SizeWE",
+    "SizeNWSE",
+    "SizeNESW",
+    "SizeAll",
+    "UpArrow",
+    "Hand",
+];
+
+// `[Strings]` names used by the common cursor pack INF template, in `CURSOR_ORDER`. Only
+// consulted when the INF does not write a scheme itself.
+const TEMPLATE_STRINGS: [&[&str]; 15] = [
+    &["pointer"],
+    &["help"],
+    &["work"],
+    &["busy"],
+    &["cross", "precision"],
+    &["text"],
+    &["hand", "handwriting"],
+    &["unavailiable", "unavailable"],
+    &["vert"],
+    &["horz"],
+    &["dgn1"],
+    &["dgn2"],
+    &["move"],
+    &["alternate"],
+    &["link"],
+];
+
+const SCHEMES_KEY: &str = r"Control Panel\Cursors\Schemes";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CursorPack {
+    pub scheme_name: String,
+    // File names, in `CURSOR_ORDER`. `None` keeps the system default for that cursor
+    pub cursors: Vec<Option<String>>,
+}
+
+struct Inf {
+    sections: HashMap<String, Vec<String>>,
+    strings: HashMap<String, String>,
+}
+
+impl Inf {
+    fn parse(contents: &str) -> Inf {
+        let mut sections: HashMap<String, Vec<String>> = HashMap::new();
+        let mut current = String::new();
+        for line in contents.lines() {
+            let line = Inf::strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                current = name.trim().to_lowercase();
+                continue;
+            }
+            sections
+                .entry(current.clone())
+                .or_default()
+                .push(line.to_string());
+        }
+        let strings = sections
+            .get("strings")
+            .map(|lines| {
+                lines
+                    .iter()
+                    .filter_map(|line| line.split_once('='))
+                    .map(|(key, value)| (key.trim().to_lowercase(), Inf::unquote(value.trim())))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Inf { sections, strings }
+    }
+    // `;` starts a comment, unless it is inside quotes
+    fn strip_comment(line: &str) -> &str {
+        let mut quoted = false;
+        for (i, c) in line.char_indices() {
+            match c {
+                '"' => quoted = !quoted,
+                ';' if !quoted => return &line[..i],
+                _ => (),
+            }
+        }
+        line
+    }
+    fn unquote(value: &str) -> String {
+        value
+            .strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+            .unwrap_or(value)
+            .to_string()
+    }
+    // Replaces `%name%` with its `[Strings]` value. Unknown names (like the `%10%` directory id) are kept
+    fn substitute(&self, value: &str) -> String {
+        let mut output = String::new();
+        let mut rest = value;
+        while let Some(start) = rest.find('%') {
+            output.push_str(&rest[..start]);
+            let after = &rest[start + 1..];
+            match after.find('%') {
+                Some(end) => {
+                    let name = &after[..end];
+                    match self.strings.get(&name.to_lowercase()) {
+                        Some(replacement) => output.push_str(replacement),
+                        None => {
+                            output.push('%');
+                            output.push_str(name);
+                            output.push('%');
+                        }
+                    }
+                    rest = &after[end + 1..];
+                }
+                None => {
+                    output.push_str(&rest[start..]);
+                    rest = "";
+                }
+            }
+        }
+        output.push_str(rest);
+        output
+    }
+    // Splits an `AddReg` line on commas that are not inside quotes
+    fn fields(line: &str) -> Vec<String> {
+        let mut fields = vec![];
+        let mut field = String::new();
+        let mut quoted = false;
+        for c in line.chars() {
+            match c {
+                '"' => quoted = !quoted,
+                ',' if !quoted => fields.push(std::mem::take(&mut field).trim().to_string()),
+                _ => field.push(c),
+            }
+        }
+        fields.push(field.trim().to_string());
+        fields
+    }
+    fn file_name(path: &str) -> Option<String> {
+        let name = path.rsplit(['\\', '/']).next()?.trim();
+        if name.is_empty() {
+            None
+        } else {
+            Some(name.to_string())
+        }
+    }
+    // Names from the INF become paths under the cursors folder, so they have to stay a single
+    // plain component: no separators, no `..` and no drive or root prefix
+    fn check_name(name: &str) -> Result<(), CursorPackError> {
+        let unsafe_name = name.trim().is_empty()
+            || name.contains(['/', '\\', ':'])
+            || name.contains("..")
+            || Path::new(name).has_root();
+        if unsafe_name {
+            Err(CursorPackError::UnsafeName {
+                name: name.to_string(),
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl CursorPack {
+    /// Reads the scheme an `install.inf` would register.
+    pub fn parse_inf(contents: &str) -> Result<CursorPack, CursorPackError> {
+        let inf = Inf::parse(contents);
+        // Most packs write the scheme with an AddReg line, which has the cursors in `CURSOR_ORDER`
+        for line in inf.sections.values().flatten() {
+            let fields = Inf::fields(line);
+            if fields.len() < 5 || !fields[1].eq_ignore_ascii_case(SCHEMES_KEY) {
+                continue;
+            }
+            let scheme_name = inf.substitute(&fields[2]);
+            // The value itself has commas in it, so put the rest back together
+            let value = inf.substitute(&fields[4..].join(","));
+            let mut cursors = value.split(',').map(Inf::file_name).collect::<Vec<_>>();
+            cursors.resize(CURSOR_ORDER.len(), None);
+            return CursorPack {
+                scheme_name,
+                cursors,
+            }
+            .checked();
+        }
+        let scheme_name = inf
+            .strings
+            .get("scheme_name")
+            .cloned()
+            .ok_or(CursorPackError::MissingScheme)?;
+        let cursors = TEMPLATE_STRINGS
+            .iter()
+            .map(|names| {
+                names
+                    .iter()
+                    .find_map(|name| inf.strings.get(*name))
+                    .and_then(|path| Inf::file_name(path))
+            })
+            .collect::<Vec<_>>();
+        if cursors.iter().all(Option::is_none) {
+            return Err(CursorPackError::MissingScheme);
+        }
+        CursorPack {
+            scheme_name,
+            cursors,
+        }
+        .checked()
+    }
+    fn checked(self) -> Result<CursorPack, CursorPackError> {
+        Inf::check_name(&self.scheme_name)?;
+        for name in self.cursors.iter().flatten() {
+            Inf::check_name(name)?;
+        }
+        Ok(self)
+    }
+    /// Where each cursor file ends up when installed into `dir`, in `CURSOR_ORDER`.
+    pub fn layout(&self, dir: &Path) -> Vec<Option<PathBuf>> {
+        self.cursors
+            .iter()
+            .map(|cursor| cursor.as_ref().map(|name| dir.join(name)))
+            .collect()
+    }
+    /// The registry value for this scheme once installed into `dir`.
+    pub fn scheme_value(&self, dir: &Path) -> String {
+        self.layout(dir)
+            .iter()
+            .map(|path| {
+                path.as_ref()
+                    .map(|path| path.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+/// Every file in a cursor pack folder or `.zip`, keyed by lowercase file name.
+pub fn read_pack_files(path: &Path) -> Result<HashMap<String, Vec<u8>>, Box<dyn Error + 'static>> {
+    let mut files = HashMap::new();
+    if path.is_dir() {
+        let mut dirs = vec![path.to_path_buf()];
+        while let Some(dir) = dirs.pop() {
+            for entry in fs::read_dir(dir)?.flatten() {
+                let entry_path = entry.path();
+                if entry_path.is_dir() {
+                    dirs.push(entry_path);
+                } else {
+                    files.insert(
+                        entry.file_name().to_string_lossy().to_lowercase(),
+                        fs::read(entry_path)?,
+                    );
+                }
+            }
+        }
+    } else {
+        let mut archive = zip::ZipArchive::new(fs::File::open(path)?)?;
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i)?;
+            if !file.is_file() {
+                continue;
+            }
+            let Some(name) = Inf::file_name(file.name()) else {
+                continue;
+            };
+            let mut contents = vec![];
+            file.read_to_end(&mut contents)?;
+            files.insert(name.to_lowercase(), contents);
+        }
+    }
+    Ok(files)
+}
+
+fn parse_pack_files(files: &HashMap<String, Vec<u8>>) -> Result<CursorPack, CursorPackError> {
+    let inf = files
+        .get("install.inf")
+        .or_else(|| {
+            files
+                .iter()
+                .find(|(name, _)| name.ends_with(".inf"))
+                .map(|(_, contents)| contents)
+        })
+        .ok_or(CursorPackError::MissingInf)?;
+    CursorPack::parse_inf(&String::from_utf8_lossy(inf))
+}
+
+/// Reads a cursor pack's scheme without installing anything.
+pub fn read_pack(path: &Path) -> Result<CursorPack, Box<dyn Error + 'static>> {
+    Ok(parse_pack_files(&read_pack_files(path)?)?)
+}
+
+/// Copies the pack's cursors into `dir/<scheme name>` and returns the scheme and the folder.
+pub fn install_pack(
+    path: &Path,
+    dir: &Path,
+) -> Result<(CursorPack, PathBuf), Box<dyn Error + 'static>> {
+    let files = read_pack_files(path)?;
+    let pack = parse_pack_files(&files)?;
+    let install_dir = dir.join(&pack.scheme_name);
+    fs::create_dir_all(&install_dir)?;
+    for (name, destination) in pack.cursors.iter().zip(pack.layout(&install_dir)) {
+        let (Some(name), Some(destination)) = (name, destination) else {
+            continue;
+        };
+        let contents = files
+            .get(&name.to_lowercase())
+            .ok_or(CursorPackError::MissingFile { name: name.clone() })?;
+        fs::write(destination, contents)?;
+    }
+    Ok((pack, install_dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The layout most packs use: the scheme is written by an AddReg line, with `[Strings]` names
+    const ADDREG_INF: &str = r#"; Miku cursors
+[Version]
+signature="$CHICAGO$"
+
+[DefaultInstall]
+CopyFiles = Scheme.Cur
+AddReg    = Scheme.Reg
+
+[DestinationDirs]
+Scheme.Cur = 10,"%CUR_DIR%"
+
+[Scheme.Reg]
+HKCU,"Control Panel\Cursors\Schemes","%SCHEME_NAME%",,"%10%\%CUR_DIR%\%pointer%,%10%\%CUR_DIR%\%help%,%10%\%CUR_DIR%\%work%,%10%\%CUR_DIR%\%busy%,,%10%\%CUR_DIR%\%text%"
+
+[Strings]
+CUR_DIR     = "Cursors\Miku"
+SCHEME_NAME = "Miku" ; shown in the mouse settings
+pointer     = "Normal.ani"
+help        = "Help.ani"
+work        = "Working; in background.ani"
+busy        = "Busy.ani"
+text        = "Text.cur"
+"#;
+
+    // Packs made from the common template only fill in `[Strings]`
+    const TEMPLATE_INF: &str = r#"[Strings]
+SCHEME_NAME = "Totoro"
+pointer     = "Totoro\Arrow.cur"
+unavailiable = "Totoro\No.cur"
+link        = "Totoro\Link.ani"
+"#;
+
+    fn names(pack: &CursorPack) -> Vec<Option<&str>> {
+        pack.cursors.iter().map(Option::as_deref).collect()
+    }
+
+    #[test]
+    fn strings_are_substituted() {
+        let inf = Inf::parse(ADDREG_INF);
+        assert_eq!(
+            inf.substitute("%10%\\%CUR_DIR%\\%Pointer%"),
+            "%10%\\Cursors\\Miku\\Normal.ani"
+        );
+        assert_eq!(inf.substitute("100% %unknown"), "100% %unknown");
+        // Comments are dropped, but a `;` inside quotes is part of the value
+        assert_eq!(inf.strings["scheme_name"], "Miku");
+        assert_eq!(inf.strings["work"], "Working; in background.ani");
+    }
+
+    #[test]
+    fn parses_addreg_scheme() {
+        let pack = CursorPack::parse_inf(ADDREG_INF).unwrap();
+        assert_eq!(pack.scheme_name, "Miku");
+        assert_eq!(pack.cursors.len(), CURSOR_ORDER.len());
+        assert_eq!(
+            names(&pack)[..7],
+            [
+                Some("Normal.ani"),
+                Some("Help.ani"),
+                Some("Working; in background.ani"),
+                Some("Busy.ani"),
+                None,
+                Some("Text.cur"),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_template_strings() {
+        let pack = CursorPack::parse_inf(TEMPLATE_INF).unwrap();
+        assert_eq!(pack.scheme_name, "Totoro");
+        let names = names(&pack);
+        assert_eq!(names[0], Some("Arrow.cur"));
+        assert_eq!(names[7], Some("No.cur"));
+        assert_eq!(names[14], Some("Link.ani"));
+        assert_eq!(names.iter().flatten().count(), 3);
+    }
+
+    #[test]
+    fn missing_scheme_is_an_error() {
+        assert!(matches!(
+            CursorPack::parse_inf("[Strings]\npointer = \"Arrow.cur\"\n"),
+            Err(CursorPackError::MissingScheme)
+        ));
+    }
+
+    #[test]
+    fn layout_and_scheme_value() {
+        let pack = CursorPack::parse_inf(TEMPLATE_INF).unwrap();
+        let dir = Path::new("cursors").join("Totoro");
+        let layout = pack.layout(&dir);
+        assert_eq!(layout[0], Some(dir.join("Arrow.cur")));
+        assert_eq!(layout[1], None);
+        let value = pack.scheme_value(&dir);
+        assert_eq!(value.split(',').count(), CURSOR_ORDER.len());
+        assert!(value.starts_with(&dir.join("Arrow.cur").to_string_lossy().to_string()));
+    }
+
+    #[test]
+    fn unsafe_names_are_rejected() {
+        for scheme_name in ["..", "..\\..\\Windows", "C:Evil", "/etc", "a/b"] {
+            let inf = TEMPLATE_INF.replace("\"Totoro\"", &format!("\"{}\"", scheme_name));
+            assert!(
+                matches!(
+                    CursorPack::parse_inf(&inf),
+                    Err(CursorPackError::UnsafeName { .. })
+                ),
+                "{}",
+                scheme_name
+            );
+        }
+        let inf = TEMPLATE_INF.replace("Totoro\\Link.ani", "..");
+        assert!(matches!(
+            CursorPack::parse_inf(&inf),
+            Err(CursorPackError::UnsafeName { .. })
+        ));
+    }
+}
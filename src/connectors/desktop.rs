@@ -0,0 +1,269 @@
+/*
+  Desktop connector
+  Name: desktop
+  Controls: icon theme and fonts on Linux desktops
+
+  Config options:
+  - Icon theme: Option<String> - Name of an installed icon theme. None = do not change
+  - UI font: Option<String> - Font family for menus, buttons and text. None = do not change
+  - Monospace font: Option<String> - Font family for terminals and code. None = do not change
+  - Size: Option<u32> - Font size in points, for both fonts. Only used together with a font. None = 11
+  NOTE: This is not a section of its own, it is set from the top-level `icon_theme` and `fonts`.
+  They are written everywhere they are read: GTK's settings.ini and gtkrc, GNOME through gsettings,
+  Plasma's kdeglobals and fontconfig's `sans-serif` and `monospace`. xsettingsd is reloaded if it runs.
+*/
+
+use std::{
+    error::Error,
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    edit::{self, ini},
+    process, xdg,
+};
+
+use super::{gtk::Gtk, Connector};
+
+custom_error::custom_error! {pub DesktopError
+    MissingHome = "could not find the home directory",
+    IconThemeNotFound{name: String} = "icon theme not installed: {name}",
+    FontNotFound{name: String} = "font not installed: {name}",
+    FontListFailed = "could not list the installed fonts with fc-list"
+}
+
+const DEFAULT_SIZE: u32 = 11;
+
+pub struct Desktop {
+    pub icon_theme: Option<String>,
+    pub ui_font: Option<String>,
+    pub monospace_font: Option<String>,
+    pub size: Option<u32>,
+}
+
+impl Desktop {
+    fn config_dir() -> Result<PathBuf, DesktopError> {
+        xdg::config_home().ok_or(DesktopError::MissingHome)
+    }
+    /// Font families fontconfig knows about.
+    pub fn get_installed_fonts() -> Option<Vec<String>> {
+        let output = process::output("fc-list", &[":", "family"])?;
+        let mut fonts = output
+            .lines()
+            // Fonts with localized names list all of them, separated by commas
+            .flat_map(|line| line.split(','))
+            .map(|family| family.trim().to_string())
+            .filter(|family| !family.is_empty())
+            .collect::<Vec<_>>();
+        fonts.sort();
+        fonts.dedup();
+        Some(fonts)
+    }
+    // GTK and GNOME want `Family Size`
+    fn pango_font(&self, family: &str) -> String {
+        format!("{} {}", family, self.size.unwrap_or(DEFAULT_SIZE))
+    }
+    // Qt's QFont::toString format, with regular weight and no styles
+    fn qt_font(&self, family: &str) -> String {
+        format!(
+            "{},{},-1,5,50,0,0,0,0,0",
+            family,
+            self.size.unwrap_or(DEFAULT_SIZE)
+        )
+    }
+    pub fn fontconfig(ui_font: &Option<String>, monospace_font: &Option<String>) -> String {
+        let escape = |family: &str| {
+            family
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+        };
+        let mut aliases = String::new();
+        for (generic, family) in [("sans-serif", ui_font), ("monospace", monospace_font)] {
+            if let Some(family) = family {
+                aliases.push_str(&format!(
+                    "  <alias>\n    <family>{}</family>\n    <prefer><family>{}</family></prefer>\n  </alias>\n",
+                    generic,
+                    escape(family)
+                ));
+            }
+        }
+        format!(
+            "<?xml version=\"1.0\"?>\n<!DOCTYPE fontconfig SYSTEM \"urn:fontconfig:fonts.dtd\">\n<!-- Generated by univeme -->\n<fontconfig>\n{}</fontconfig>\n",
+            aliases
+        )
+    }
+    fn gtk_settings(&self, contents: &str) -> String {
+        let mut contents = contents.to_string();
+        if let Some(icon_theme) = &self.icon_theme {
+            contents = ini::set(
+                &contents,
+                Some("Settings"),
+                "gtk-icon-theme-name",
+                icon_theme,
+            );
+        }
+        if let Some(ui_font) = &self.ui_font {
+            contents = ini::set(
+                &contents,
+                Some("Settings"),
+                "gtk-font-name",
+                &self.pango_font(ui_font),
+            );
+        }
+        contents
+    }
+    fn gtk2_settings(&self, contents: &str) -> String {
+        let mut contents = contents.to_string();
+        if let Some(icon_theme) = &self.icon_theme {
+            contents = ini::set(
+                &contents,
+                None,
+                "gtk-icon-theme-name",
+                &format!("\"{}\"", icon_theme),
+            );
+        }
+        if let Some(ui_font) = &self.ui_font {
+            contents = ini::set(
+                &contents,
+                None,
+                "gtk-font-name",
+                &format!("\"{}\"", self.pango_font(ui_font)),
+            );
+        }
+        contents
+    }
+    fn kdeglobals(&self, contents: &str) -> String {
+        let mut contents = contents.to_string();
+        if let Some(icon_theme) = &self.icon_theme {
+            contents = ini::set(&contents, Some("Icons"), "Theme", icon_theme);
+        }
+        if let Some(ui_font) = &self.ui_font {
+            let font = self.qt_font(ui_font);
+            for key in ["font", "menuFont", "toolBarFont"] {
+                contents = ini::set(&contents, Some("General"), key, &font);
+            }
+        }
+        if let Some(monospace_font) = &self.monospace_font {
+            contents = ini::set(
+                &contents,
+                Some("General"),
+                "fixed",
+                &self.qt_font(monospace_font),
+            );
+        }
+        contents
+    }
+    fn set_gnome(&self) {
+        let mut settings = vec![];
+        if let Some(icon_theme) = &self.icon_theme {
+            settings.push(("icon-theme", icon_theme.clone()));
+        }
+        if let Some(ui_font) = &self.ui_font {
+            settings.push(("font-name", self.pango_font(ui_font)));
+        }
+        if let Some(monospace_font) = &self.monospace_font {
+            settings.push(("monospace-font-name", self.pango_font(monospace_font)));
+        }
+        for (key, value) in settings {
+            process::run(
+                "gsettings",
+                &["set", "org.gnome.desktop.interface", key, &value],
+            );
+        }
+    }
+    fn set_xsettingsd(&self, config_dir: &Path) -> io::Result<()> {
+        edit::file(
+            &config_dir.join("xsettingsd").join("xsettingsd.conf"),
+            |contents| {
+                let mut contents = contents.to_string();
+                if let Some(icon_theme) = &self.icon_theme {
+                    contents = Gtk::set_xsetting(
+                        &contents,
+                        "Net/IconThemeName",
+                        &format!("\"{}\"", icon_theme),
+                    );
+                }
+                if let Some(ui_font) = &self.ui_font {
+                    contents = Gtk::set_xsetting(
+                        &contents,
+                        "Gtk/FontName",
+                        &format!("\"{}\"", self.pango_font(ui_font)),
+                    );
+                }
+                contents
+            },
+        )?;
+        process::run("pkill", &["-HUP", "-x", "xsettingsd"]);
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl Connector for Desktop {
+    fn new() -> Result<Self, Box<dyn Error + 'static>> {
+        Ok(Self {
+            icon_theme: None,
+            ui_font: None,
+            monospace_font: None,
+            size: None,
+        })
+    }
+    fn verify(&self) -> Result<(), Box<dyn Error + 'static>> {
+        let _ = Desktop::config_dir()?;
+        if let Some(icon_theme) = &self.icon_theme {
+            if !xdg::icon_themes().contains(icon_theme) {
+                return Err(DesktopError::IconThemeNotFound {
+                    name: icon_theme.clone(),
+                }
+                .into());
+            }
+        }
+        if self.ui_font.is_some() || self.monospace_font.is_some() {
+            let fonts = Desktop::get_installed_fonts().ok_or(DesktopError::FontListFailed)?;
+            for font in [&self.ui_font, &self.monospace_font].into_iter().flatten() {
+                if !fonts.contains(font) {
+                    return Err(DesktopError::FontNotFound { name: font.clone() }.into());
+                }
+            }
+        }
+        Ok(())
+    }
+    async fn apply(&self) -> Result<(), Box<dyn Error + 'static>> {
+        if self.icon_theme.is_none() && self.ui_font.is_none() && self.monospace_font.is_none() {
+            return Ok(());
+        }
+        let config_dir = Desktop::config_dir()?;
+        for version in ["gtk-3.0", "gtk-4.0"] {
+            edit::file(&config_dir.join(version).join("settings.ini"), |contents| {
+                self.gtk_settings(contents)
+            })?;
+        }
+        if self.icon_theme.is_some() || self.ui_font.is_some() {
+            let home = dirs::home_dir().ok_or(DesktopError::MissingHome)?;
+            edit::file(&home.join(".gtkrc-2.0"), |contents| {
+                self.gtk2_settings(contents)
+            })?;
+            if process::is_running("xsettingsd") {
+                self.set_xsettingsd(&config_dir)?;
+            }
+        }
+        self.set_gnome();
+        // Only touch kdeglobals on systems that have one, it means Plasma or KDE apps are in use
+        let kdeglobals = config_dir.join("kdeglobals");
+        if kdeglobals.exists() {
+            edit::file(&kdeglobals, |contents| self.kdeglobals(contents))?;
+        }
+        if self.ui_font.is_some() || self.monospace_font.is_some() {
+            edit::file(
+                &config_dir
+                    .join("fontconfig")
+                    .join("conf.d")
+                    .join("50-univeme.conf"),
+                |_| Desktop::fontconfig(&self.ui_font, &self.monospace_font),
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,319 @@
+/*
+  Discord connector
+  Name: discord
+  Controls: Discord theme, through Vencord, Vesktop or BetterDiscord
+
+  Config options:
+  - Installs: Vec<Install> - Client mods to install the theme into.
+  - Theme: Option<DiscordTheme> - A theme generated from the palette, or a theme file to copy. None = do not change
+  NOTE: Discord has no themes of its own, so this needs one of the client mods. The theme is always
+  installed as `univeme.theme.css`, so switching presets replaces it instead of piling up themes.
+  NOTE 2: Vencord and Vesktop pick up theme changes right away. BetterDiscord and enabling a theme
+  for the first time may need Discord to be restarted.
+*/
+
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use serde_json::{json, Value};
+
+use crate::{
+    edit,
+    palette::{Color, Palette},
+};
+
+use super::Connector;
+
+custom_error::custom_error! {pub DiscordError
+    NoClientMods = "could not find Vencord, Vesktop or BetterDiscord",
+    UnknownClientMod{name: String} = "unknown client mod (expected vencord, vesktop or betterdiscord): {name}",
+    ClientModNotFound{name: String} = "client mod is not installed: {name}",
+    MissingThemeName = "BetterDiscord themes need a `@name` in their header",
+    MalformedSettings{path: String} = "client mod settings are not valid JSON: {path}"
+}
+
+/// File name every theme is installed as.
+pub const THEME_FILE: &str = "univeme.theme.css";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClientMod {
+    Vencord,
+    Vesktop,
+    BetterDiscord,
+}
+
+impl FromStr for ClientMod {
+    type Err = DiscordError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "vencord" => Ok(ClientMod::Vencord),
+            "vesktop" => Ok(ClientMod::Vesktop),
+            "betterdiscord" => Ok(ClientMod::BetterDiscord),
+            _ => Err(DiscordError::UnknownClientMod {
+                name: s.to_string(),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Install {
+    pub client_mod: ClientMod,
+    // The mod's own folder, with `themes` in it
+    pub dir: PathBuf,
+}
+
+pub enum DiscordTheme {
+    Palette {
+        palette: Palette,
+        name: String,
+        author: String,
+    },
+    File(PathBuf),
+}
+
+pub struct Discord {
+    pub installs: Vec<Install>,
+    pub theme: Option<DiscordTheme>,
+}
+
+impl Install {
+    fn settings_file(&self) -> PathBuf {
+        match self.client_mod {
+            ClientMod::Vencord | ClientMod::Vesktop => {
+                self.dir.join("settings").join("settings.json")
+            }
+            ClientMod::BetterDiscord => self.dir.join("data").join("stable").join("themes.json"),
+        }
+    }
+}
+
+impl Discord {
+    pub fn get_installs() -> Vec<Install> {
+        let mut candidates = vec![];
+        if let Some(config) = dirs::config_dir() {
+            candidates.push((ClientMod::Vencord, config.join("Vencord")));
+            candidates.push((ClientMod::Vesktop, config.join("vesktop")));
+            candidates.push((ClientMod::BetterDiscord, config.join("BetterDiscord")));
+        }
+        #[cfg(all(unix, not(target_os = "macos")))]
+        if let Some(home) = dirs::home_dir() {
+            candidates.push((
+                ClientMod::Vesktop,
+                home.join(".var/app/dev.vencord.Vesktop/config/vesktop"),
+            ));
+        }
+        candidates
+            .into_iter()
+            .filter(|(_, dir)| dir.is_dir())
+            .map(|(client_mod, dir)| Install { client_mod, dir })
+            .collect()
+    }
+    /// The installs of the given client mods, or all of them when there are none.
+    pub fn select(
+        installs: Vec<Install>,
+        client_mods: &Option<Vec<ClientMod>>,
+    ) -> Result<Vec<Install>, DiscordError> {
+        let Some(client_mods) = client_mods else {
+            return Ok(installs);
+        };
+        for client_mod in client_mods {
+            if !installs
+                .iter()
+                .any(|install| install.client_mod == *client_mod)
+            {
+                return Err(DiscordError::ClientModNotFound {
+                    name: format!("{:?}", client_mod).to_lowercase(),
+                });
+            }
+        }
+        Ok(installs
+            .into_iter()
+            .filter(|install| client_mods.contains(&install.client_mod))
+            .collect())
+    }
+    /// A theme that recolors Discord with the palette. The header is what BetterDiscord and
+    /// Vencord show in their theme lists.
+    pub fn generate_css(palette: &Palette, name: &str, author: &str) -> String {
+        let surface = palette.surface();
+        let background = palette.background;
+        let foreground = palette.foreground;
+        let accent = palette.accent;
+        let variables = [
+            ("--background-primary", background),
+            ("--background-secondary", surface),
+            ("--background-secondary-alt", surface.mix(&foreground, 0.05)),
+            ("--background-tertiary", background.mix(&Color::BLACK, 0.15)),
+            ("--background-floating", surface),
+            (
+                "--background-base-lowest",
+                background.mix(&Color::BLACK, 0.15),
+            ),
+            ("--background-base-lower", surface),
+            ("--background-base-low", surface),
+            ("--background-surface-high", surface),
+            (
+                "--background-surface-higher",
+                surface.mix(&foreground, 0.05),
+            ),
+            ("--chat-background-default", background),
+            ("--channeltextarea-background", surface),
+            ("--modal-background", background),
+            (
+                "--background-modifier-hover",
+                background.mix(&foreground, 0.08),
+            ),
+            (
+                "--background-modifier-active",
+                background.mix(&foreground, 0.12),
+            ),
+            (
+                "--background-modifier-selected",
+                background.mix(&foreground, 0.16),
+            ),
+            ("--text-normal", foreground),
+            ("--text-default", foreground),
+            ("--text-muted", foreground.mix(&background, 0.4)),
+            ("--header-primary", foreground),
+            ("--header-secondary", foreground.mix(&background, 0.2)),
+            ("--interactive-normal", foreground.mix(&background, 0.2)),
+            ("--interactive-hover", foreground),
+            ("--interactive-active", foreground),
+            ("--interactive-muted", foreground.mix(&background, 0.6)),
+            ("--channels-default", foreground.mix(&background, 0.3)),
+            ("--text-link", accent),
+            ("--brand-500", accent),
+            ("--brand-560", accent.mix(&Color::BLACK, 0.1)),
+            ("--brand-experiment", accent),
+            ("--button-filled-brand-background", accent),
+            ("--button-filled-brand-text", palette.accent_foreground()),
+            ("--scrollbar-auto-thumb", surface.mix(&foreground, 0.2)),
+            ("--scrollbar-auto-track", background),
+        ];
+        let mut css = format!(
+            "/**\n * @name {}\n * @author {}\n * @description Generated by univeme from the preset palette\n * @version 1.0.0\n */\n\n",
+            name, author
+        );
+        css.push_str(":root,\n.theme-dark,\n.theme-light,\n.visual-refresh {\n");
+        for (variable, color) in variables {
+            css.push_str(&format!("  {}: {} !important;\n", variable, color));
+        }
+        css.push_str("}\n");
+        css
+    }
+    /// The `@name` in a theme's header, which BetterDiscord keys themes by.
+    pub fn theme_name(css: &str) -> Option<String> {
+        let header_end = css.find("*/")?;
+        css[..header_end].lines().find_map(|line| {
+            let name = line.trim().trim_start_matches('*').trim();
+            let name = name.strip_prefix("@name")?.trim();
+            (!name.is_empty()).then(|| name.to_string())
+        })
+    }
+    /// Adds the theme to `enabledThemes` in the contents of Vencord's `settings.json`.
+    pub fn enable_vencord(settings: &str) -> Option<String> {
+        let mut settings: Value = if settings.trim().is_empty() {
+            json!({})
+        } else {
+            serde_json::from_str(settings).ok()?
+        };
+        let settings_object = settings.as_object_mut()?;
+        let enabled = settings_object
+            .entry("enabledThemes")
+            .or_insert_with(|| json!([]))
+            .as_array_mut()?;
+        if !enabled.iter().any(|theme| theme == THEME_FILE) {
+            enabled.push(json!(THEME_FILE));
+        }
+        serde_json::to_string_pretty(&settings).ok()
+    }
+    /// Turns the theme on in the contents of BetterDiscord's `themes.json`.
+    pub fn enable_betterdiscord(themes: &str, name: &str) -> Option<String> {
+        let mut themes: Value = if themes.trim().is_empty() {
+            json!({})
+        } else {
+            serde_json::from_str(themes).ok()?
+        };
+        themes
+            .as_object_mut()?
+            .insert(name.to_string(), Value::Bool(true));
+        serde_json::to_string_pretty(&themes).ok()
+    }
+    fn theme_css(theme: &DiscordTheme) -> Result<String, Box<dyn Error + 'static>> {
+        Ok(match theme {
+            DiscordTheme::Palette {
+                palette,
+                name,
+                author,
+            } => Discord::generate_css(palette, name, author),
+            DiscordTheme::File(path) => fs::read_to_string(path)?,
+        })
+    }
+    fn enable(install: &Install, css: &str) -> Result<(), Box<dyn Error + 'static>> {
+        let path = install.settings_file();
+        let contents = edit::read(&path)?;
+        let malformed = || DiscordError::MalformedSettings {
+            path: path.to_string_lossy().to_string(),
+        };
+        let contents = match install.client_mod {
+            ClientMod::Vencord | ClientMod::Vesktop => {
+                Discord::enable_vencord(&contents).ok_or_else(malformed)?
+            }
+            ClientMod::BetterDiscord => {
+                let name = Discord::theme_name(css).ok_or(DiscordError::MissingThemeName)?;
+                Discord::enable_betterdiscord(&contents, &name).ok_or_else(malformed)?
+            }
+        };
+        write(&path, &contents)
+    }
+}
+
+fn write(path: &Path, contents: &str) -> Result<(), Box<dyn Error + 'static>> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, contents)?;
+    Ok(())
+}
+
+#[async_trait::async_trait]
+impl Connector for Discord {
+    fn new() -> Result<Self, Box<dyn Error + 'static>> {
+        Ok(Self {
+            installs: Discord::get_installs(),
+            theme: None,
+        })
+    }
+    fn verify(&self) -> Result<(), Box<dyn Error + 'static>> {
+        if self.installs.is_empty() {
+            return Err(DiscordError::NoClientMods.into());
+        }
+        if let Some(theme) = &self.theme {
+            let css = Discord::theme_css(theme)?;
+            if self
+                .installs
+                .iter()
+                .any(|install| install.client_mod == ClientMod::BetterDiscord)
+                && Discord::theme_name(&css).is_none()
+            {
+                return Err(DiscordError::MissingThemeName.into());
+            }
+        }
+        Ok(())
+    }
+    async fn apply(&self) -> Result<(), Box<dyn Error + 'static>> {
+        let Some(theme) = &self.theme else {
+            return Ok(());
+        };
+        let css = Discord::theme_css(theme)?;
+        for install in &self.installs {
+            write(&install.dir.join("themes").join(THEME_FILE), &css)?;
+            Discord::enable(install, &css)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,154 @@
+/*
+  Firefox connector
+  Name: firefox
+  Controls: browser colors, dark mode, default theme
+
+  Config options:
+  - Profiles: Vec<Profile> - Firefox profiles to change, from `profiles.ini`.
+  - Palette: Option<Palette> - Colors for the browser UI and about: pages, through userChrome.css and userContent.css. None = do not change
+  - Dark: Option<bool> - Whether Firefox and websites should use dark mode. None = do not change
+  - Theme ID: Option<String> - Add-on ID of an installed theme, like `firefox-compact-dark@mozilla.org`. None = do not change
+  NOTE: Unlike pprefox, this only edits profile files, so it works anywhere. Firefox reads them on start,
+  so it has to be restarted to see the changes, which main reports. Setting the theme while Firefox is
+  running is an error before anything is written, since Firefox overwrites it when it exits.
+*/
+
+use std::{error::Error, path::PathBuf};
+
+use crate::{palette::Palette, process};
+
+use super::{
+    mozilla::{self, Profile, ProfileChanges},
+    Connector,
+};
+
+custom_error::custom_error! {pub FirefoxError
+    NoProfiles = "could not find any Firefox profiles"
+}
+
+pub struct Firefox {
+    pub profiles: Vec<Profile>,
+    pub palette: Option<Palette>,
+    pub dark: Option<bool>,
+    pub theme_id: Option<String>,
+}
+
+impl Firefox {
+    /// Folders with a `profiles.ini`, for every way Firefox is commonly installed.
+    fn roots() -> Vec<PathBuf> {
+        let mut roots = vec![];
+        #[cfg(windows)]
+        if let Some(app_data) = dirs::config_dir() {
+            roots.push(app_data.join("Mozilla").join("Firefox"));
+        }
+        #[cfg(target_os = "macos")]
+        if let Some(app_support) = dirs::config_dir() {
+            roots.push(app_support.join("Firefox"));
+        }
+        #[cfg(all(unix, not(target_os = "macos")))]
+        if let Some(home) = dirs::home_dir() {
+            roots.push(home.join(".mozilla").join("firefox"));
+            roots.push(home.join(".var/app/org.mozilla.firefox/.mozilla/firefox"));
+            roots.push(home.join("snap/firefox/common/.mozilla/firefox"));
+        }
+        roots
+    }
+    pub fn get_profiles() -> Vec<Profile> {
+        mozilla::profiles(&Firefox::roots())
+    }
+    /// Whether files were changed that Firefox only reads when it starts, while it is running.
+    pub fn needs_restart(&self) -> bool {
+        (self.palette.is_some() || self.dark.is_some()) && Firefox::is_running()
+    }
+    pub fn is_running() -> bool {
+        process::is_running("firefox") || process::is_running("firefox-bin")
+    }
+    pub fn user_chrome(palette: &Palette) -> String {
+        let surface = palette.surface();
+        format!(
+            ":root {{
+  --lwt-accent-color: {background} !important;
+  --lwt-text-color: {foreground} !important;
+  --toolbar-bgcolor: {surface} !important;
+  --toolbar-color: {foreground} !important;
+  --toolbarbutton-icon-fill: {foreground} !important;
+  --tab-selected-bgcolor: {surface} !important;
+  --tab-selected-textcolor: {foreground} !important;
+  --tab-loading-fill: {accent} !important;
+  --toolbar-field-background-color: {background} !important;
+  --toolbar-field-color: {foreground} !important;
+  --toolbar-field-focus-background-color: {background} !important;
+  --toolbar-field-focus-color: {foreground} !important;
+  --toolbar-field-focus-border-color: {accent} !important;
+  --focus-outline-color: {accent} !important;
+  --arrowpanel-background: {surface} !important;
+  --arrowpanel-color: {foreground} !important;
+  --sidebar-background-color: {surface} !important;
+  --sidebar-text-color: {foreground} !important;
+}}
+",
+            background = palette.background,
+            foreground = palette.foreground,
+            accent = palette.accent,
+            surface = surface,
+        )
+    }
+    pub fn user_content(palette: &Palette) -> String {
+        format!(
+            "@-moz-document url-prefix(\"about:\") {{
+  :root {{
+    --in-content-page-background: {background} !important;
+    --in-content-page-color: {foreground} !important;
+    --in-content-box-background: {surface} !important;
+    --in-content-text-color: {foreground} !important;
+    --in-content-primary-button-background: {accent} !important;
+    --in-content-primary-button-text-color: {accent_foreground} !important;
+    --in-content-accent-color: {accent} !important;
+  }}
+}}
+",
+            background = palette.background,
+            foreground = palette.foreground,
+            accent = palette.accent,
+            accent_foreground = palette.accent_foreground(),
+            surface = palette.surface(),
+        )
+    }
+}
+
+#[async_trait::async_trait]
+impl Connector for Firefox {
+    fn new() -> Result<Self, Box<dyn Error + 'static>> {
+        Ok(Self {
+            profiles: Firefox::get_profiles(),
+            palette: None,
+            dark: None,
+            theme_id: None,
+        })
+    }
+    fn verify(&self) -> Result<(), Box<dyn Error + 'static>> {
+        if self.profiles.is_empty() {
+            return Err(FirefoxError::NoProfiles.into());
+        }
+        if let Some(theme_id) = &self.theme_id {
+            mozilla::verify_theme(&self.profiles, theme_id)?;
+        }
+        mozilla::verify_not_running("Firefox", Firefox::is_running(), &self.theme_id)?;
+        Ok(())
+    }
+    async fn apply(&self) -> Result<(), Box<dyn Error + 'static>> {
+        let mut chrome = vec![];
+        if let Some(palette) = &self.palette {
+            chrome.push(("userChrome.css", Firefox::user_chrome(palette)));
+            chrome.push(("userContent.css", Firefox::user_content(palette)));
+        }
+        mozilla::apply(
+            &self.profiles,
+            &ProfileChanges {
+                prefs: mozilla::style_prefs(self.palette.is_some(), self.dark),
+                chrome,
+                theme_id: self.theme_id.as_deref(),
+            },
+        )
+    }
+}
@@ -0,0 +1,179 @@
+/*
+  GTK connector
+  Name: gtk
+  Controls: GTK 2/3/4 theme, libadwaita colors
+
+  Config options:
+  - Theme: Option<String> - Name of an installed GTK theme. None = do not change
+  - Prefer dark: Option<bool> - Whether apps should use the dark variant of the theme. None = do not change
+  - Libadwaita palette: Option<Palette> - Colors to override libadwaita's with, through gtk.css. None = do not change
+  NOTE: This writes the same files a settings daemon would, so it works without a desktop environment.
+  If xsettingsd is running, it is updated and reloaded so open apps switch immediately.
+*/
+
+use std::{error::Error, fs, path::PathBuf};
+
+use crate::{
+    edit::{self, block, ini},
+    palette::Palette,
+    process, xdg,
+};
+
+use super::Connector;
+
+custom_error::custom_error! {pub GtkError
+    MissingHome = "could not find the home directory",
+    ThemeNotFound{name: String} = "GTK theme not installed: {name}"
+}
+
+pub struct Gtk {
+    pub theme: Option<String>,
+    pub prefer_dark: Option<bool>,
+    pub libadwaita_palette: Option<Palette>,
+}
+
+impl Gtk {
+    fn config_dir() -> Result<PathBuf, GtkError> {
+        dirs::config_dir().ok_or(GtkError::MissingHome)
+    }
+    fn theme_dirs() -> Vec<PathBuf> {
+        let mut theme_dirs = vec![];
+        if let Some(home) = dirs::home_dir() {
+            theme_dirs.push(home.join(".themes"));
+        }
+        theme_dirs.extend(xdg::data_dirs().into_iter().map(|data| data.join("themes")));
+        theme_dirs
+    }
+    pub fn get_installed_themes() -> Vec<String> {
+        let mut themes = vec![];
+        for theme_dir in Gtk::theme_dirs() {
+            let Ok(entries) = fs::read_dir(theme_dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                // Icon and cursor themes can live in the same folders, so only count real GTK themes
+                if ["gtk-2.0", "gtk-3.0", "gtk-4.0"]
+                    .iter()
+                    .any(|version| path.join(version).is_dir())
+                {
+                    themes.push(entry.file_name().to_string_lossy().to_string());
+                }
+            }
+        }
+        themes.sort();
+        themes.dedup();
+        themes
+    }
+    fn libadwaita_css(palette: &Palette) -> String {
+        let surface = palette.surface();
+        [
+            ("accent_color", palette.accent),
+            ("accent_bg_color", palette.accent),
+            ("accent_fg_color", palette.accent_foreground()),
+            ("window_bg_color", palette.background),
+            ("window_fg_color", palette.foreground),
+            ("view_bg_color", palette.background),
+            ("view_fg_color", palette.foreground),
+            ("headerbar_bg_color", surface),
+            ("headerbar_fg_color", palette.foreground),
+            ("sidebar_bg_color", surface),
+            ("sidebar_fg_color", palette.foreground),
+            ("popover_bg_color", surface),
+            ("popover_fg_color", palette.foreground),
+            ("dialog_bg_color", surface),
+            ("dialog_fg_color", palette.foreground),
+        ]
+        .iter()
+        .map(|(name, color)| format!("@define-color {} {};\n", name, color))
+        .collect()
+    }
+    // xsettingsd uses `Name value` lines instead of `name=value`
+    pub fn set_xsetting(contents: &str, key: &str, value: &str) -> String {
+        let mut found = false;
+        let mut lines = contents
+            .lines()
+            .map(|line| match line.split_whitespace().next() {
+                Some(name) if name == key => {
+                    found = true;
+                    format!("{} {}", key, value)
+                }
+                _ => line.to_string(),
+            })
+            .collect::<Vec<_>>();
+        if !found {
+            lines.push(format!("{} {}", key, value));
+        }
+        lines.join("\n") + "\n"
+    }
+}
+
+#[async_trait::async_trait]
+impl Connector for Gtk {
+    fn new() -> Result<Self, Box<dyn Error + 'static>> {
+        Ok(Self {
+            theme: None,
+            prefer_dark: None,
+            libadwaita_palette: None,
+        })
+    }
+    fn verify(&self) -> Result<(), Box<dyn Error + 'static>> {
+        let _ = Gtk::config_dir()?;
+        if let Some(theme) = &self.theme {
+            if !Gtk::get_installed_themes().contains(theme) {
+                return Err(GtkError::ThemeNotFound {
+                    name: theme.clone(),
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+    async fn apply(&self) -> Result<(), Box<dyn Error + 'static>> {
+        let config_dir = Gtk::config_dir()?;
+        for version in ["gtk-3.0", "gtk-4.0"] {
+            edit::file(&config_dir.join(version).join("settings.ini"), |contents| {
+                let mut contents = contents.to_string();
+                if let Some(theme) = &self.theme {
+                    contents = ini::set(&contents, Some("Settings"), "gtk-theme-name", theme);
+                }
+                if let Some(prefer_dark) = self.prefer_dark {
+                    contents = ini::set(
+                        &contents,
+                        Some("Settings"),
+                        "gtk-application-prefer-dark-theme",
+                        if prefer_dark { "1" } else { "0" },
+                    );
+                }
+                contents
+            })?;
+            if let Some(palette) = &self.libadwaita_palette {
+                edit::file(&config_dir.join(version).join("gtk.css"), |contents| {
+                    block::replace(
+                        contents,
+                        &block::CSS,
+                        "libadwaita",
+                        &Gtk::libadwaita_css(palette),
+                    )
+                })?;
+            }
+        }
+        if let Some(theme) = &self.theme {
+            let home = dirs::home_dir().ok_or(GtkError::MissingHome)?;
+            // GTK 2 has no sections and wants the value quoted
+            edit::file(&home.join(".gtkrc-2.0"), |contents| {
+                ini::set(contents, None, "gtk-theme-name", &format!("\"{}\"", theme))
+            })?;
+            if process::is_running("xsettingsd") {
+                edit::file(
+                    &config_dir.join("xsettingsd").join("xsettingsd.conf"),
+                    |contents| {
+                        Gtk::set_xsetting(contents, "Net/ThemeName", &format!("\"{}\"", theme))
+                    },
+                )?;
+                process::run("pkill", &["-HUP", "-x", "xsettingsd"]);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,252 @@
+/*
+  HTTP connector
+  Name: http
+  Controls: anything with an HTTP API
+
+  Config options:
+  - Method: Method - HTTP method of the request.
+  - URL: String - Where to send the request. A template, like the body.
+  - Headers: Vec<(String, String)> - Extra request headers. Values are templates.
+  - Body: Option<String> - JSON body, as a Jinja template (see `template`). Must render to valid JSON. None = no body
+  - Expected status: Option<u16> - Status code that counts as success. None = any 2xx
+  - Context: Value - What the templates can use. Has `id` once a lookup found one.
+  NOTE: Like ledfx, the lookup runs while the preset is loaded: it GETs a list from the API and finds the
+  ID of the entry with a given name, so presets can refer to things by name instead of by ID.
+*/
+
+use std::{collections::HashMap, error::Error};
+
+use reqwest::{header::CONTENT_TYPE, Method, Url};
+use serde_json::{Map, Value};
+
+use super::{template, Connector};
+
+custom_error::custom_error! {pub HttpError
+    InvalidBody{reason: String} = "http body is not valid JSON: {reason}",
+    UnexpectedStatus{url: String, status: u16} = "http request to {url} returned status {status}",
+    LookupFailed{url: String} = "http lookup at {url} did not return a list or object",
+    NameNotFound{name: String} = "http lookup found nothing named {name}",
+    ContextNotObject = "http context is not an object, so the looked up id can not be added to it"
+}
+
+/// Finds an ID by name in a list the API returns.
+pub struct Lookup {
+    pub url: String,
+    // JSON pointer to the list, like `/scenes`. Empty for the whole response
+    pub pointer: String,
+    // Field with the ID in each entry. None = the entries' keys, when the list is an object
+    pub id_field: Option<String>,
+    pub name_field: String,
+}
+
+pub struct Http {
+    pub method: Method,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+    pub expected_status: Option<u16>,
+    pub context: Value,
+}
+
+impl Http {
+    /// Every entry of the lookup's list, as name to ID.
+    pub fn lookup_ids(response: &Value, lookup: &Lookup) -> Option<HashMap<String, String>> {
+        let id_string = |value: &Value| match value {
+            Value::String(id) => Some(id.clone()),
+            Value::Number(id) => Some(id.to_string()),
+            _ => None,
+        };
+        let entry = |key: Option<&String>, entry: &Value| {
+            let name = entry.get(&lookup.name_field)?.as_str()?.to_string();
+            let id = match &lookup.id_field {
+                Some(id_field) => id_string(entry.get(id_field)?)?,
+                None => key?.clone(),
+            };
+            Some((name, id))
+        };
+        match response.pointer(&lookup.pointer)? {
+            Value::Object(entries) => Some(
+                entries
+                    .iter()
+                    .filter_map(|(key, value)| entry(Some(key), value))
+                    .collect(),
+            ),
+            Value::Array(entries) => Some(
+                entries
+                    .iter()
+                    .filter_map(|value| entry(None, value))
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+    pub async fn get_available_ids(
+        &self,
+        lookup: &Lookup,
+    ) -> Result<HashMap<String, String>, Box<dyn Error + 'static>> {
+        let url = Url::parse(&template::render(&lookup.url, &self.context)?)?;
+        let response = reqwest::get(url.clone()).await?.json::<Value>().await?;
+        Ok(
+            Http::lookup_ids(&response, lookup).ok_or(HttpError::LookupFailed {
+                url: url.to_string(),
+            })?,
+        )
+    }
+    /// Looks up `name` and makes its ID available to the templates as `id`.
+    pub async fn resolve_id(
+        &mut self,
+        lookup: &Lookup,
+        name: &str,
+    ) -> Result<(), Box<dyn Error + 'static>> {
+        let ids = self.get_available_ids(lookup).await?;
+        let id = ids.get(name).ok_or(HttpError::NameNotFound {
+            name: name.to_string(),
+        })?;
+        let Value::Object(context) = &mut self.context else {
+            return Err(HttpError::ContextNotObject.into());
+        };
+        context.insert("id".to_string(), Value::from(id.clone()));
+        Ok(())
+    }
+    fn url(&self) -> Result<Url, Box<dyn Error + 'static>> {
+        Ok(Url::parse(&template::render(&self.url, &self.context)?)?)
+    }
+    fn body(&self) -> Result<Option<String>, Box<dyn Error + 'static>> {
+        let Some(body) = &self.body else {
+            return Ok(None);
+        };
+        let body = template::render(body, &self.context)?;
+        if let Err(error) = serde_json::from_str::<Value>(&body) {
+            return Err(HttpError::InvalidBody {
+                reason: error.to_string(),
+            }
+            .into());
+        }
+        Ok(Some(body))
+    }
+    fn is_expected(&self, status: u16) -> bool {
+        match self.expected_status {
+            Some(expected_status) => status == expected_status,
+            None => (200..300).contains(&status),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Connector for Http {
+    fn new() -> Result<Self, Box<dyn Error + 'static>> {
+        Ok(Self {
+            method: Method::POST,
+            url: String::new(),
+            headers: vec![],
+            body: None,
+            expected_status: None,
+            context: Value::Object(Map::new()),
+        })
+    }
+    fn verify(&self) -> Result<(), Box<dyn Error + 'static>> {
+        let _ = self.url()?;
+        let _ = self.body()?;
+        for (_, value) in &self.headers {
+            template::render(value, &self.context)?;
+        }
+        Ok(())
+    }
+    async fn apply(&self) -> Result<(), Box<dyn Error + 'static>> {
+        let url = self.url()?;
+        let mut request = reqwest::Client::builder()
+            .build()?
+            .request(self.method.clone(), url.clone());
+        for (name, value) in &self.headers {
+            request = request.header(name, template::render(value, &self.context)?);
+        }
+        if let Some(body) = self.body()? {
+            request = request.header(CONTENT_TYPE, "application/json").body(body);
+        }
+        let status = request.send().await?.status().as_u16();
+        if !self.is_expected(status) {
+            return Err(HttpError::UnexpectedStatus {
+                url: url.to_string(),
+                status,
+            }
+            .into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn lookup(pointer: &str, id_field: Option<&str>) -> Lookup {
+        Lookup {
+            url: "http://localhost/".to_string(),
+            pointer: pointer.to_string(),
+            id_field: id_field.map(str::to_string),
+            name_field: "name".to_string(),
+        }
+    }
+
+    fn ids(entries: &[(&str, &str)]) -> Option<HashMap<String, String>> {
+        Some(
+            entries
+                .iter()
+                .map(|(name, id)| (name.to_string(), id.to_string()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn array_with_string_and_number_ids() {
+        let response = json!([
+            {"name": "Movie", "id": "scene-1"},
+            {"name": "Night", "id": 7},
+            {"name": "No id"},
+            {"id": "no-name"},
+        ]);
+        assert_eq!(
+            Http::lookup_ids(&response, &lookup("", Some("id"))),
+            ids(&[("Movie", "scene-1"), ("Night", "7")])
+        );
+    }
+
+    #[test]
+    fn object_keyed_by_id() {
+        let response = json!({"scenes": {"a1": {"name": "Movie"}, "b2": {"name": "Night"}}});
+        assert_eq!(
+            Http::lookup_ids(&response, &lookup("/scenes", None)),
+            ids(&[("Movie", "a1"), ("Night", "b2")])
+        );
+        // An ID field wins over the key
+        let response = json!({"scenes": {"a1": {"name": "Movie", "uid": 3}}});
+        assert_eq!(
+            Http::lookup_ids(&response, &lookup("/scenes", Some("uid"))),
+            ids(&[("Movie", "3")])
+        );
+    }
+
+    #[test]
+    fn array_without_an_id_field_has_no_ids() {
+        let response = json!({"data": {"scenes": [{"name": "Movie"}]}});
+        assert_eq!(
+            Http::lookup_ids(&response, &lookup("/data/scenes", None)),
+            ids(&[])
+        );
+    }
+
+    #[test]
+    fn pointer_has_to_reach_a_list() {
+        let response = json!({"scenes": "none", "count": 2});
+        assert_eq!(Http::lookup_ids(&response, &lookup("/scenes", None)), None);
+        assert_eq!(Http::lookup_ids(&response, &lookup("/missing", None)), None);
+    }
+
+    #[test]
+    fn new_context_is_an_object() {
+        let http = Http::new().unwrap();
+        assert_eq!(http.context, json!({}));
+    }
+}
@@ -0,0 +1,213 @@
+/*
+  Launcher connector
+  Name: launcher
+  Controls: rofi, fuzzel and wofi colors
+
+  Config options:
+  - Rofi: Option<Palette> - Colors for a generated `univeme` theme in rofi's themes folder, which config.rasi selects. None = do not change
+  - Fuzzel: Option<Palette> - Colors for the `[colors]` section of fuzzel.ini. None = do not change
+  - Wofi: Option<Palette> - Colors for a managed block at the end of wofi's style.css. None = do not change
+  NOTE: Launchers read their config every time they open, so nothing has to be reloaded.
+*/
+
+use std::{error::Error, path::PathBuf};
+
+use crate::{
+    edit::{self, block, ini},
+    palette::{Color, Palette},
+    xdg,
+};
+
+use super::Connector;
+
+custom_error::custom_error! {pub LauncherError
+    MissingHome = "could not find the home folder"
+}
+
+pub struct Launcher {
+    pub rofi: Option<Palette>,
+    pub fuzzel: Option<Palette>,
+    pub wofi: Option<Palette>,
+}
+
+impl Launcher {
+    fn config_home() -> Result<PathBuf, LauncherError> {
+        xdg::config_home().ok_or(LauncherError::MissingHome)
+    }
+    pub fn rofi_theme(palette: &Palette) -> String {
+        format!(
+            "/* Generated by univeme from the preset palette */
+* {{
+    bg: {background};
+    fg: {foreground};
+    surface: {surface};
+    accent: {accent};
+    accent-fg: {accent_foreground};
+    muted: {muted};
+    background-color: transparent;
+    text-color: @fg;
+}}
+
+window {{
+    background-color: @bg;
+    border: 2px;
+    border-color: @accent;
+    border-radius: 8px;
+    padding: 12px;
+    width: 40%;
+}}
+
+inputbar {{
+    background-color: @surface;
+    border-radius: 6px;
+    padding: 8px;
+    spacing: 8px;
+    children: [ prompt, entry ];
+}}
+
+prompt {{
+    text-color: @accent;
+}}
+
+entry {{
+    placeholder-color: @muted;
+}}
+
+listview {{
+    lines: 8;
+    margin: 8px 0 0 0;
+    spacing: 2px;
+}}
+
+element {{
+    padding: 6px 8px;
+    border-radius: 6px;
+}}
+
+element selected {{
+    background-color: @accent;
+    text-color: @accent-fg;
+}}
+
+element-text, element-icon {{
+    text-color: inherit;
+}}
+",
+            background = palette.background,
+            foreground = palette.foreground,
+            surface = palette.surface(),
+            accent = palette.accent,
+            accent_foreground = palette.accent_foreground(),
+            muted = palette.foreground.mix(&palette.background, 0.5),
+        )
+    }
+    /// Keys of fuzzel's `[colors]` section, as `rrggbbaa`.
+    pub fn fuzzel_colors(palette: &Palette) -> Vec<(&'static str, String)> {
+        let opaque = |color: Color| format!("{}ff", color.hex_bare());
+        let accent = palette.accent;
+        vec![
+            ("background", opaque(palette.background)),
+            ("text", opaque(palette.foreground)),
+            ("prompt", opaque(accent)),
+            ("input", opaque(palette.foreground)),
+            (
+                "placeholder",
+                opaque(palette.foreground.mix(&palette.background, 0.5)),
+            ),
+            ("match", opaque(accent)),
+            ("selection", opaque(accent)),
+            ("selection-text", opaque(palette.accent_foreground())),
+            ("selection-match", opaque(palette.accent_foreground())),
+            (
+                "counter",
+                opaque(palette.foreground.mix(&palette.background, 0.5)),
+            ),
+            ("border", opaque(accent)),
+        ]
+    }
+    pub fn wofi_css(palette: &Palette) -> String {
+        format!(
+            "window {{
+    background-color: {background};
+    color: {foreground};
+    border: 2px solid {accent};
+}}
+
+#outer-box, #inner-box, #scroll {{
+    background-color: {background};
+}}
+
+#input {{
+    background-color: {surface};
+    color: {foreground};
+    border: none;
+}}
+
+#text {{
+    color: {foreground};
+}}
+
+#entry:selected {{
+    background-color: {accent};
+}}
+
+#entry:selected #text {{
+    color: {accent_foreground};
+}}
+",
+            background = palette.background,
+            foreground = palette.foreground,
+            surface = palette.surface(),
+            accent = palette.accent,
+            accent_foreground = palette.accent_foreground(),
+        )
+    }
+}
+
+#[async_trait::async_trait]
+impl Connector for Launcher {
+    fn new() -> Result<Self, Box<dyn Error + 'static>> {
+        Ok(Self {
+            rofi: None,
+            fuzzel: None,
+            wofi: None,
+        })
+    }
+    fn verify(&self) -> Result<(), Box<dyn Error + 'static>> {
+        let _ = Launcher::config_home()?;
+        Ok(())
+    }
+    async fn apply(&self) -> Result<(), Box<dyn Error + 'static>> {
+        let config_home = Launcher::config_home()?;
+        if let Some(palette) = &self.rofi {
+            let rofi_dir = config_home.join("rofi");
+            edit::file(&rofi_dir.join("themes").join("univeme.rasi"), |_| {
+                Launcher::rofi_theme(palette)
+            })?;
+            // The block goes at the end of config.rasi, so it wins over an `@theme` above it
+            edit::file(&rofi_dir.join("config.rasi"), |contents| {
+                block::replace(contents, &block::CSS, "theme", "@theme \"univeme\"")
+            })?;
+        }
+        if let Some(palette) = &self.fuzzel {
+            edit::file(&config_home.join("fuzzel").join("fuzzel.ini"), |contents| {
+                Launcher::fuzzel_colors(palette)
+                    .iter()
+                    .fold(contents.to_string(), |contents, (key, value)| {
+                        ini::set(&contents, Some("colors"), key, value)
+                    })
+            })?;
+        }
+        if let Some(palette) = &self.wofi {
+            edit::file(&config_home.join("wofi").join("style.css"), |contents| {
+                block::replace(
+                    contents,
+                    &block::CSS,
+                    "palette",
+                    &Launcher::wofi_css(palette),
+                )
+            })?;
+        }
+        Ok(())
+    }
+}
@@ -1,11 +1,46 @@
 use std::error::Error;
 
+#[cfg(unix)]
+pub mod bar;
+pub mod chromium;
+pub mod cli;
+pub mod cursor_pack;
+#[cfg(unix)]
+pub mod desktop;
+pub mod discord;
+pub mod firefox;
+#[cfg(unix)]
+pub mod gtk;
+pub mod http;
+#[cfg(unix)]
+pub mod launcher;
 pub mod ledfx;
+pub mod mozilla;
+#[cfg(unix)]
+pub mod notifications;
+pub mod obsidian;
 pub mod pprefox;
-#[cfg(windows)]
+#[cfg(unix)]
+pub mod qt;
+pub mod registry_backend;
+pub mod spicetify;
+pub mod template;
+pub mod thunderbird;
+#[cfg(unix)]
+pub mod tmux;
+pub mod vscode;
 pub mod windows;
 #[cfg(windows)]
 pub mod wpeng;
+pub mod wpeng_control;
+pub mod wpeng_library;
+pub mod wpeng_monitor;
+#[cfg(unix)]
+pub mod xcursor;
+#[cfg(unix)]
+pub mod xresources;
+#[cfg(unix)]
+pub mod zellij;
 
 #[async_trait::async_trait]
 pub trait Connector
@@ -0,0 +1,280 @@
+/*
+  Mozilla profile support
+  Used by: firefox, thunderbird
+
+  Finds profiles through `profiles.ini` and edits the files inside them. Everything univeme
+  writes to `user.js` and the `chrome` folder is kept in a managed block, so the user's own
+  prefs and styles are left alone.
+*/
+
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde_json::Value;
+
+use crate::edit::{self, block, ini};
+
+custom_error::custom_error! {pub MozillaError
+    ProfileNotFound{name: String} = "profile not found: {name}",
+    ThemeNotInstalled{id: String} = "theme is not installed in this profile: {id}",
+    MalformedExtensions = "extensions.json is not valid",
+    AppRunning{name: String} = "{name} is running, close it first so it does not overwrite the theme"
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    pub name: String,
+    pub path: PathBuf,
+    // The profile the app opens without being asked for another one
+    pub default: bool,
+}
+
+/// Reads the profiles listed in a `profiles.ini` found in `root`.
+pub fn parse_profiles_ini(contents: &str, root: &Path) -> Vec<Profile> {
+    let sections = ini::sections(contents);
+    // Newer versions keep a default per installation, older ones mark the profile itself
+    let install_defaults = sections
+        .iter()
+        .filter(|section| section.starts_with("Install"))
+        .filter_map(|section| ini::get(contents, Some(section), "Default"))
+        .collect::<Vec<_>>();
+    sections
+        .iter()
+        .filter(|section| section.starts_with("Profile"))
+        .filter_map(|section| {
+            let get = |key| ini::get(contents, Some(section), key);
+            let relative_path = get("Path")?;
+            let path = if get("IsRelative").as_deref() == Some("0") {
+                PathBuf::from(&relative_path)
+            } else {
+                root.join(&relative_path)
+            };
+            Some(Profile {
+                name: get("Name").unwrap_or_else(|| relative_path.clone()),
+                default: if install_defaults.is_empty() {
+                    get("Default").as_deref() == Some("1")
+                } else {
+                    install_defaults.contains(&relative_path)
+                },
+                path,
+            })
+        })
+        .collect()
+}
+
+/// Every profile in every root that has a `profiles.ini`.
+pub fn profiles(roots: &[PathBuf]) -> Vec<Profile> {
+    roots
+        .iter()
+        .filter_map(|root| {
+            let contents = fs::read_to_string(root.join("profiles.ini")).ok()?;
+            Some(parse_profiles_ini(&contents, root))
+        })
+        .flatten()
+        .filter(|profile| profile.path.is_dir())
+        .collect()
+}
+
+/// The profiles with the given names, or all of them when there are none.
+pub fn select(
+    profiles: Vec<Profile>,
+    names: &Option<Vec<String>>,
+) -> Result<Vec<Profile>, MozillaError> {
+    let Some(names) = names else {
+        return Ok(profiles);
+    };
+    names
+        .iter()
+        .map(|name| {
+            profiles
+                .iter()
+                .find(|profile| profile.name.eq_ignore_ascii_case(name))
+                .cloned()
+                .ok_or_else(|| MozillaError::ProfileNotFound { name: name.clone() })
+        })
+        .collect()
+}
+
+fn pref_line(key: &str, value: &Value) -> String {
+    format!("user_pref({}, {});", Value::String(key.to_string()), value)
+}
+
+/// Writes `prefs` to the profile's `user.js`, which the app reads on every start.
+pub fn set_user_prefs(
+    profile: &Path,
+    prefs: &[(&str, Value)],
+) -> Result<(), Box<dyn Error + 'static>> {
+    let body = prefs
+        .iter()
+        .map(|(key, value)| pref_line(key, value) + "\n")
+        .collect::<String>();
+    edit::file(&profile.join("user.js"), |contents| {
+        block::replace(contents, &block::JS, "prefs", &body)
+    })?;
+    Ok(())
+}
+
+/// Puts `css` in the profile's `chrome/<file>`, like `userChrome.css`.
+pub fn set_chrome_css(
+    profile: &Path,
+    file: &str,
+    css: &str,
+) -> Result<(), Box<dyn Error + 'static>> {
+    edit::file(&profile.join("chrome").join(file), |contents| {
+        block::replace(contents, &block::CSS, "palette", css)
+    })?;
+    Ok(())
+}
+
+/// Sets a pref in the contents of `prefs.js`, replacing the line for it if there is one.
+pub fn set_pref(prefs_js: &str, key: &str, value: &Value) -> String {
+    let prefix = format!("user_pref({},", Value::String(key.to_string()));
+    let line = pref_line(key, value);
+    let mut lines = prefs_js.lines().map(str::to_string).collect::<Vec<_>>();
+    match lines
+        .iter_mut()
+        .find(|existing| existing.starts_with(&prefix))
+    {
+        Some(existing) => *existing = line,
+        None => lines.push(line),
+    }
+    let mut output = lines.join("\n");
+    output.push('\n');
+    output
+}
+
+/// Marks `id` as the only enabled theme in the contents of `extensions.json`.
+pub fn activate_theme(extensions_json: &str, id: &str) -> Result<String, MozillaError> {
+    let mut extensions: Value =
+        serde_json::from_str(extensions_json).map_err(|_| MozillaError::MalformedExtensions)?;
+    let addons = extensions
+        .get_mut("addons")
+        .and_then(Value::as_array_mut)
+        .ok_or(MozillaError::MalformedExtensions)?;
+    let mut found = false;
+    for addon in addons
+        .iter_mut()
+        .filter(|addon| addon.get("type").and_then(Value::as_str) == Some("theme"))
+    {
+        let active = addon.get("id").and_then(Value::as_str) == Some(id);
+        found |= active;
+        addon["active"] = Value::Bool(active);
+        addon["userDisabled"] = Value::Bool(!active);
+    }
+    if !found {
+        return Err(MozillaError::ThemeNotInstalled { id: id.to_string() });
+    }
+    Ok(extensions.to_string())
+}
+
+/// IDs of the themes installed in a profile.
+pub fn installed_themes(profile: &Path) -> Vec<String> {
+    let Ok(contents) = fs::read_to_string(profile.join("extensions.json")) else {
+        return vec![];
+    };
+    let Ok(extensions) = serde_json::from_str::<Value>(&contents) else {
+        return vec![];
+    };
+    extensions
+        .get("addons")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter(|addon| addon.get("type").and_then(Value::as_str) == Some("theme"))
+        .filter_map(|addon| addon.get("id").and_then(Value::as_str))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Switches the profile's theme. The app rewrites both files when it exits, so this only
+/// sticks while it is closed.
+pub fn set_active_theme(profile: &Path, id: &str) -> Result<(), Box<dyn Error + 'static>> {
+    let extensions = profile.join("extensions.json");
+    if extensions.exists() {
+        let contents = activate_theme(&fs::read_to_string(&extensions)?, id)?;
+        fs::write(&extensions, contents)?;
+    }
+    edit::file(&profile.join("prefs.js"), |contents| {
+        set_pref(
+            contents,
+            "extensions.activeThemeID",
+            &Value::String(id.to_string()),
+        )
+    })?;
+    Ok(())
+}
+
+/// The `user.js` prefs for chrome styles and dark mode.
+pub fn style_prefs(stylesheets: bool, dark: Option<bool>) -> Vec<(&'static str, Value)> {
+    let mut prefs = vec![];
+    if stylesheets {
+        // Without this userChrome.css and userContent.css are ignored
+        prefs.push((
+            "toolkit.legacyUserProfileCustomizations.stylesheets",
+            Value::Bool(true),
+        ));
+    }
+    if let Some(dark) = dark {
+        prefs.push(("ui.systemUsesDarkTheme", Value::from(u8::from(dark))));
+    }
+    prefs
+}
+
+/// Checks `theme_id` is installed in every profile that has been opened before.
+pub fn verify_theme(profiles: &[Profile], theme_id: &str) -> Result<(), MozillaError> {
+    for profile in profiles {
+        let themes = installed_themes(&profile.path);
+        // A profile that was never opened has no list of themes yet
+        if !themes.is_empty() && !themes.iter().any(|theme| theme == theme_id) {
+            return Err(MozillaError::ThemeNotInstalled {
+                id: theme_id.to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Everything a connector changes in a profile.
+pub struct ProfileChanges<'a> {
+    pub prefs: Vec<(&'a str, Value)>,
+    // File in `chrome` and the CSS for it
+    pub chrome: Vec<(&'a str, String)>,
+    pub theme_id: Option<&'a str>,
+}
+
+/// The theme can not be switched while the app is running, since it would be overwritten when it
+/// exits. `app` is only used for the error.
+pub fn verify_not_running(
+    app: &str,
+    running: bool,
+    theme_id: &Option<String>,
+) -> Result<(), MozillaError> {
+    if running && theme_id.is_some() {
+        return Err(MozillaError::AppRunning {
+            name: app.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Writes `changes` to every profile.
+pub fn apply(
+    profiles: &[Profile],
+    changes: &ProfileChanges,
+) -> Result<(), Box<dyn Error + 'static>> {
+    for profile in profiles {
+        if !changes.prefs.is_empty() {
+            set_user_prefs(&profile.path, &changes.prefs)?;
+        }
+        for (file, css) in &changes.chrome {
+            set_chrome_css(&profile.path, file, css)?;
+        }
+        if let Some(theme_id) = changes.theme_id {
+            set_active_theme(&profile.path, theme_id)?;
+        }
+    }
+    Ok(())
+}
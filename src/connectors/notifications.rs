@@ -0,0 +1,125 @@
+/*
+  Notifications connector
+  Name: notifications
+  Controls: dunst and mako colors
+
+  Config options:
+  - Dunst: Option<Palette> - Colors for dunstrc's `[global]` and urgency sections. None = do not change
+  - Mako: Option<Palette> - Colors for mako's config, including `[urgency=critical]`. None = do not change
+  NOTE: Only the color keys are written, the rest of each config stays as it was. Both daemons are told
+  to reload if they are running, so the next notification has the new colors.
+*/
+
+use std::{error::Error, path::PathBuf};
+
+use crate::{
+    edit::{self, ini},
+    palette::{Color, Palette},
+    process, xdg,
+};
+
+use super::Connector;
+
+custom_error::custom_error! {pub NotificationsError
+    MissingHome = "could not find the home folder"
+}
+
+pub struct Notifications {
+    pub dunst: Option<Palette>,
+    pub mako: Option<Palette>,
+}
+
+impl Notifications {
+    fn config_home() -> Result<PathBuf, NotificationsError> {
+        xdg::config_home().ok_or(NotificationsError::MissingHome)
+    }
+    // Critical notifications stand out with the palette's red, when it has one
+    fn critical(palette: &Palette) -> Color {
+        palette.role("color1").unwrap_or(palette.accent)
+    }
+    /// `(section, key, value)` for every color in dunstrc.
+    pub fn dunst_colors(palette: &Palette) -> Vec<(&'static str, &'static str, String)> {
+        let quoted = |color| format!("\"{}\"", color);
+        let background = quoted(palette.background);
+        let foreground = quoted(palette.foreground);
+        let mut colors = vec![
+            ("global", "frame_color", quoted(palette.accent)),
+            ("global", "separator_color", "\"frame\"".to_string()),
+            ("global", "highlight", quoted(palette.accent)),
+        ];
+        for section in ["urgency_low", "urgency_normal"] {
+            colors.push((section, "background", background.clone()));
+            colors.push((section, "foreground", foreground.clone()));
+            colors.push((section, "frame_color", quoted(palette.accent)));
+        }
+        colors.push(("urgency_critical", "background", background));
+        colors.push(("urgency_critical", "foreground", foreground));
+        colors.push((
+            "urgency_critical",
+            "frame_color",
+            quoted(Notifications::critical(palette)),
+        ));
+        colors
+    }
+    /// `(section, key, value)` for every color in mako's config. `None` is the top level.
+    pub fn mako_colors(palette: &Palette) -> Vec<(Option<&'static str>, &'static str, String)> {
+        vec![
+            (None, "background-color", palette.background.to_string()),
+            (None, "text-color", palette.foreground.to_string()),
+            (None, "border-color", palette.accent.to_string()),
+            (
+                None,
+                "progress-color",
+                format!("over {}", palette.surface()),
+            ),
+            (
+                Some("urgency=critical"),
+                "border-color",
+                Notifications::critical(palette).to_string(),
+            ),
+        ]
+    }
+}
+
+#[async_trait::async_trait]
+impl Connector for Notifications {
+    fn new() -> Result<Self, Box<dyn Error + 'static>> {
+        Ok(Self {
+            dunst: None,
+            mako: None,
+        })
+    }
+    fn verify(&self) -> Result<(), Box<dyn Error + 'static>> {
+        let _ = Notifications::config_home()?;
+        Ok(())
+    }
+    async fn apply(&self) -> Result<(), Box<dyn Error + 'static>> {
+        let config_home = Notifications::config_home()?;
+        if let Some(palette) = &self.dunst {
+            edit::file(&config_home.join("dunst").join("dunstrc"), |contents| {
+                Notifications::dunst_colors(palette).iter().fold(
+                    contents.to_string(),
+                    |contents, (section, key, value)| {
+                        ini::set(&contents, Some(section), key, value)
+                    },
+                )
+            })?;
+            if process::is_running("dunst") {
+                process::run("dunstctl", &["reload"]);
+            }
+        }
+        if let Some(palette) = &self.mako {
+            edit::file(&config_home.join("mako").join("config"), |contents| {
+                Notifications::mako_colors(palette)
+                    .iter()
+                    .fold(contents.to_string(), |contents, (section, key, value)| {
+                        ini::set(&contents, *section, key, value)
+                    })
+            })?;
+            if process::is_running("mako") {
+                process::run("makoctl", &["reload"]);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,221 @@
+/*
+  Obsidian connector
+  Name: obsidian
+  Controls: vault theme, light/dark mode, accent color
+
+  Config options:
+  - Vaults: Vec<PathBuf> - Vault folders, the ones with `.obsidian` in them.
+  - CSS theme: Option<String> - Name of a community theme installed in the vault, "" for the default theme. None = do not change
+  - Dark: Option<bool> - Whether the vault should use the dark base theme. None = do not change
+  - Palette: Option<Palette> - Accent colors, through `accentColor` and a CSS snippet that gets turned on. None = do not change
+  NOTE: Every vault has its own settings and themes, so a theme has to be installed in each vault it is used in.
+  Open vaults pick up the changes after "Reload app without saving" or a restart.
+*/
+
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde_json::Value;
+
+use crate::{
+    edit::{self, jsonc},
+    palette::{Color, Palette},
+};
+
+use super::Connector;
+
+custom_error::custom_error! {pub ObsidianError
+    NoVaults = "could not find any Obsidian vaults",
+    VaultNotFound{path: String} = "not an Obsidian vault (no .obsidian folder): {path}",
+    ThemeNotFound{name: String, vault: String} = "Obsidian theme {name} is not installed in vault {vault}",
+    MalformedAppearance{path: String} = "appearance.json is not valid JSON: {path}"
+}
+
+/// Name of the CSS snippet made from the palette.
+pub const SNIPPET_NAME: &str = "univeme";
+
+pub struct Obsidian {
+    pub vaults: Vec<PathBuf>,
+    pub css_theme: Option<String>,
+    pub dark: Option<bool>,
+    pub palette: Option<Palette>,
+}
+
+impl Obsidian {
+    /// Vaults Obsidian knows about, from its `obsidian.json`.
+    pub fn get_vaults() -> Vec<PathBuf> {
+        let mut candidates = vec![];
+        if let Some(config) = dirs::config_dir() {
+            candidates.push(config.join("obsidian"));
+        }
+        #[cfg(all(unix, not(target_os = "macos")))]
+        if let Some(home) = dirs::home_dir() {
+            candidates.push(home.join(".var/app/md.obsidian.Obsidian/config/obsidian"));
+        }
+        candidates
+            .iter()
+            .filter_map(|dir| fs::read_to_string(dir.join("obsidian.json")).ok())
+            .flat_map(|contents| Obsidian::parse_vaults(&contents))
+            .collect()
+    }
+    /// The vault paths in the contents of `obsidian.json`.
+    pub fn parse_vaults(contents: &str) -> Vec<PathBuf> {
+        let Ok(value) = serde_json::from_str::<Value>(contents) else {
+            return vec![];
+        };
+        let Some(vaults) = value.get("vaults").and_then(Value::as_object) else {
+            return vec![];
+        };
+        vaults
+            .values()
+            .filter_map(|vault| vault.get("path")?.as_str())
+            .map(PathBuf::from)
+            .collect()
+    }
+    /// Community themes installed in a vault. Newer themes are folders with a `theme.css`,
+    /// older ones a single `<name>.css`.
+    pub fn get_installed_themes(vault: &Path) -> Vec<String> {
+        let Ok(entries) = fs::read_dir(vault.join(".obsidian").join("themes")) else {
+            return vec![];
+        };
+        let mut themes = entries
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.path();
+                if path.join("theme.css").is_file() {
+                    Some(entry.file_name().to_string_lossy().to_string())
+                } else if path.extension().is_some_and(|extension| extension == "css") {
+                    Some(path.file_stem()?.to_string_lossy().to_string())
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        themes.sort();
+        themes.dedup();
+        themes
+    }
+    pub fn snippet_css(palette: &Palette) -> String {
+        let accent = palette.accent;
+        format!(
+            "/* Generated by univeme from the preset palette */
+.theme-light,
+.theme-dark {{
+  --color-accent: {accent};
+  --color-accent-1: {accent_hover};
+  --color-accent-2: {accent_active};
+  --interactive-accent: {accent};
+  --interactive-accent-hover: {accent_hover};
+  --text-accent: {accent};
+  --text-accent-hover: {accent_hover};
+  --text-on-accent: {accent_foreground};
+  --text-selection: {selection};
+}}
+",
+            accent = accent,
+            accent_hover = accent.mix(&Color::WHITE, 0.15),
+            accent_active = accent.mix(&Color::BLACK, 0.15),
+            accent_foreground = palette.accent_foreground(),
+            selection = accent.mix(&palette.background, 0.6),
+        )
+    }
+    /// `enabledCssSnippets` from the contents of `appearance.json`, with the palette snippet added.
+    pub fn enabled_snippets(appearance: &str) -> Option<Value> {
+        let mut snippets = if appearance.trim().is_empty() {
+            vec![]
+        } else {
+            let appearance: Value = serde_json::from_str(appearance).ok()?;
+            match appearance.get("enabledCssSnippets") {
+                Some(snippets) => snippets.as_array()?.clone(),
+                None => vec![],
+            }
+        };
+        if !snippets.iter().any(|snippet| snippet == SNIPPET_NAME) {
+            snippets.push(Value::from(SNIPPET_NAME));
+        }
+        Some(Value::Array(snippets))
+    }
+    fn apply_vault(&self, vault: &Path) -> Result<(), Box<dyn Error + 'static>> {
+        let config = vault.join(".obsidian");
+        let appearance_path = config.join("appearance.json");
+        let mut appearance = edit::read(&appearance_path)?;
+        let malformed = || ObsidianError::MalformedAppearance {
+            path: appearance_path.to_string_lossy().to_string(),
+        };
+        if let Some(css_theme) = &self.css_theme {
+            appearance = jsonc::set_top_level(&appearance, "cssTheme", &css_theme.as_str().into())
+                .map_err(|_| malformed())?;
+        }
+        if let Some(dark) = self.dark {
+            // Obsidian calls its dark base theme `obsidian` and the light one `moonstone`
+            let theme = if dark { "obsidian" } else { "moonstone" };
+            appearance = jsonc::set_top_level(&appearance, "theme", &theme.into())
+                .map_err(|_| malformed())?;
+        }
+        if let Some(palette) = &self.palette {
+            edit::file(
+                &config
+                    .join("snippets")
+                    .join(format!("{}.css", SNIPPET_NAME)),
+                |_| Obsidian::snippet_css(palette),
+            )?;
+            let snippets = Obsidian::enabled_snippets(&appearance).ok_or_else(malformed)?;
+            appearance = jsonc::set_top_level(&appearance, "enabledCssSnippets", &snippets)
+                .map_err(|_| malformed())?;
+            appearance = jsonc::set_top_level(
+                &appearance,
+                "accentColor",
+                &palette.accent.to_string().into(),
+            )
+            .map_err(|_| malformed())?;
+        }
+        edit::file(&appearance_path, |_| appearance)?;
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl Connector for Obsidian {
+    fn new() -> Result<Self, Box<dyn Error + 'static>> {
+        Ok(Self {
+            vaults: Obsidian::get_vaults(),
+            css_theme: None,
+            dark: None,
+            palette: None,
+        })
+    }
+    fn verify(&self) -> Result<(), Box<dyn Error + 'static>> {
+        if self.vaults.is_empty() {
+            return Err(ObsidianError::NoVaults.into());
+        }
+        for vault in &self.vaults {
+            if !vault.join(".obsidian").is_dir() {
+                return Err(ObsidianError::VaultNotFound {
+                    path: vault.to_string_lossy().to_string(),
+                }
+                .into());
+            }
+            if let Some(css_theme) = &self.css_theme {
+                if !css_theme.is_empty()
+                    && !Obsidian::get_installed_themes(vault).contains(css_theme)
+                {
+                    return Err(ObsidianError::ThemeNotFound {
+                        name: css_theme.clone(),
+                        vault: vault.to_string_lossy().to_string(),
+                    }
+                    .into());
+                }
+            }
+        }
+        Ok(())
+    }
+    async fn apply(&self) -> Result<(), Box<dyn Error + 'static>> {
+        for vault in &self.vaults {
+            self.apply_vault(vault)?;
+        }
+        Ok(())
+    }
+}
@@ -4,24 +4,51 @@
   Controls: browser theme
 
   Config options:
-  - Endpoint: Option<String> - The URL where pprefox-rs is listening.
-  - Theme ID: Option<String> - The ID of the theme to set. None to leave unchanged.
+  - Instances: Vec<Instance> - Every pprefox-rs host to theme, one per Firefox profile.
+  - Theme name: Option<String> - The name of the theme to set. None to leave unchanged.
+  - Theme IDs: HashMap<String, String> - The theme's ID in each profile, filled in by `resolve_theme`.
+  NOTE: Each Firefox profile runs its own pprefox-rs host. `discover` finds them through the
+  instances file (which names them) and by scanning ports on this machine (which does not).
+  NOTE 2: One profile failing does not stop the others. Profiles that can not be reached while looking up
+  the theme are dropped and returned to main to report, and the apply error lists every profile that failed.
 */
 
-use reqwest::Url;
+use reqwest::{Client, Url};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, error::Error};
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fs,
+    ops::RangeInclusive,
+    path::PathBuf,
+    str::FromStr,
+    time::Duration,
+};
 
 use super::Connector;
 
 custom_error::custom_error! {pub PprefoxError
   MissingEndpoint = "No pprefox endpoint selected",
-  ExtensionFailure = "Extension request failed"
+  ExtensionFailure = "Extension request failed",
+  ThemeNotFound{name: String} = "Firefox theme not found: {name}",
+  ProfileNotFound{name: String} = "No pprefox instance for Firefox profile: {name}",
+  InvalidInstancesFile{reason: String} = "Invalid pprefox instances file: {reason}",
+  ProfilesFailed{profiles: String} = "Could not set the Firefox theme for: {profiles}"
+}
+
+// A host that is not there should not hold up the scan
+const PROBE_TIMEOUT: Duration = Duration::from_millis(300);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instance {
+    pub profile: String,
+    pub endpoint: Url,
 }
 
 pub struct Pprefox {
-    pub endpoint: Option<Url>,
-    pub theme_id: Option<String>,
+    pub instances: Vec<Instance>,
+    pub theme_name: Option<String>,
+    pub theme_ids: HashMap<String, String>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -30,17 +57,111 @@ struct Theme {
     pub id: String,
 }
 
+#[derive(Deserialize)]
+struct InstancesFile {
+    profiles: BTreeMap<String, String>,
+}
+
 impl Pprefox {
-    fn get_endpoint(&self) -> Result<Url, Box<dyn Error + 'static>> {
-        match &self.endpoint {
-            Some(endpoint) => Ok(endpoint.clone()),
-            None => Err(PprefoxError::MissingEndpoint.into()),
+    /// Ports scanned when no endpoint is given. pprefox-rs listens on 8080 by default.
+    pub const DEFAULT_PORTS: RangeInclusive<u16> = 8080..=8099;
+
+    /// Where Firefox profiles can be given names, as a `[profiles]` table of name to endpoint.
+    pub fn instances_file() -> Option<PathBuf> {
+        Some(dirs::config_dir()?.join("univeme").join("pprefox.toml"))
+    }
+    pub fn parse_instances_file(contents: &str) -> Result<Vec<Instance>, PprefoxError> {
+        let file: InstancesFile =
+            toml::from_str(contents).map_err(|e| PprefoxError::InvalidInstancesFile {
+                reason: e.to_string(),
+            })?;
+        file.profiles
+            .into_iter()
+            .map(|(profile, endpoint)| {
+                Url::from_str(&endpoint)
+                    .map(|endpoint| Instance { profile, endpoint })
+                    .map_err(|e| PprefoxError::InvalidInstancesFile {
+                        reason: e.to_string(),
+                    })
+            })
+            .collect()
+    }
+    /// The hosts answering on `ports` on this machine, named after their address.
+    pub async fn scan(ports: RangeInclusive<u16>) -> Vec<Instance> {
+        let Ok(client) = Client::builder().timeout(PROBE_TIMEOUT).build() else {
+            return vec![];
+        };
+        let mut probes = tokio::task::JoinSet::new();
+        for port in ports {
+            let client = client.clone();
+            probes.spawn(async move {
+                let endpoint = Url::from_str(&format!("http://127.0.0.1:{}/", port)).ok()?;
+                let response = client
+                    .get(endpoint.join("/get_themes").ok()?)
+                    .send()
+                    .await
+                    .ok()?;
+                // Only count it if it answers like pprefox-rs would
+                response.json::<Vec<Theme>>().await.ok()?;
+                Some(Instance {
+                    profile: format!("127.0.0.1:{}", port),
+                    endpoint,
+                })
+            });
+        }
+        let mut instances = vec![];
+        while let Some(probe) = probes.join_next().await {
+            if let Ok(Some(instance)) = probe {
+                instances.push(instance);
+            }
         }
+        instances.sort_by_key(|instance| instance.endpoint.port());
+        instances
+    }
+    /// Every host that can be found: named ones from the instances file, then any others
+    /// found by scanning `ports`.
+    pub async fn discover(
+        ports: RangeInclusive<u16>,
+    ) -> Result<Vec<Instance>, Box<dyn Error + 'static>> {
+        let mut instances = match Pprefox::instances_file() {
+            Some(path) if path.exists() => {
+                Pprefox::parse_instances_file(&fs::read_to_string(path)?)?
+            }
+            _ => vec![],
+        };
+        for scanned in Pprefox::scan(ports).await {
+            if !instances
+                .iter()
+                .any(|instance| instance.endpoint == scanned.endpoint)
+            {
+                instances.push(scanned);
+            }
+        }
+        Ok(instances)
+    }
+    /// The instances for the given profile names, or all of them when there are none.
+    pub fn select(
+        instances: Vec<Instance>,
+        profiles: &Option<Vec<String>>,
+    ) -> Result<Vec<Instance>, PprefoxError> {
+        let Some(profiles) = profiles else {
+            return Ok(instances);
+        };
+        profiles
+            .iter()
+            .map(|name| {
+                instances
+                    .iter()
+                    .find(|instance| instance.profile.eq_ignore_ascii_case(name))
+                    .cloned()
+                    .ok_or_else(|| PprefoxError::ProfileNotFound { name: name.clone() })
+            })
+            .collect()
     }
     pub async fn get_available_themes(
-        &self,
+        endpoint: &Url,
     ) -> Result<HashMap<String, String>, Box<dyn Error + 'static>> {
-        let url = self.get_endpoint()?.join("/get_themes")?;
+        let url = endpoint.join("/get_themes")?;
         let resp = reqwest::get(url).await?.json::<Vec<Theme>>().await;
         // Use name as the hash and ID as the value, so we can index by name
         match resp {
@@ -51,32 +172,141 @@ impl Pprefox {
             Err(_) => Err(PprefoxError::ExtensionFailure.into()),
         }
     }
+    /// Looks up the theme's ID in every profile, since each one has its own themes installed.
+    /// Profiles that can not be reached are left out, and returned as "profile (error)".
+    pub async fn resolve_theme(&mut self) -> Result<Vec<String>, PprefoxError> {
+        let Some(theme_name) = &self.theme_name else {
+            return Ok(vec![]);
+        };
+        let mut skipped = vec![];
+        let mut reached = vec![];
+        for instance in self.instances.drain(..) {
+            let themes = match Pprefox::get_available_themes(&instance.endpoint).await {
+                Ok(themes) => themes,
+                Err(e) => {
+                    skipped.push(format!("{} ({})", instance.profile, e));
+                    continue;
+                }
+            };
+            let id = themes.get(theme_name).ok_or(PprefoxError::ThemeNotFound {
+                name: format!("{} (profile {})", theme_name, instance.profile),
+            })?;
+            self.theme_ids.insert(instance.profile.clone(), id.clone());
+            reached.push(instance);
+        }
+        self.instances = reached;
+        Ok(skipped)
+    }
+    async fn set_theme(endpoint: &Url, theme_id: &str) -> Result<(), Box<dyn Error + 'static>> {
+        let mut url = endpoint.join("/set_theme")?;
+        url.set_query(Some(&("id=".to_owned() + theme_id)));
+        reqwest::get(url).await?;
+        Ok(())
+    }
 }
 
 #[async_trait::async_trait]
 impl Connector for Pprefox {
     fn new() -> Result<Self, Box<dyn Error + 'static>> {
         Ok(Self {
-            endpoint: None,
-            theme_id: None,
+            instances: vec![],
+            theme_name: None,
+            theme_ids: HashMap::new(),
         })
     }
     fn verify(&self) -> Result<(), Box<dyn Error + 'static>> {
-        if self.endpoint.is_some() {
-            Ok(())
-        } else {
-            Err(PprefoxError::MissingEndpoint.into())
+        if self.instances.is_empty() {
+            return Err(PprefoxError::MissingEndpoint.into());
+        }
+        if let Some(theme_name) = &self.theme_name {
+            if let Some(instance) = self
+                .instances
+                .iter()
+                .find(|instance| !self.theme_ids.contains_key(&instance.profile))
+            {
+                return Err(PprefoxError::ThemeNotFound {
+                    name: format!("{} (profile {})", theme_name, instance.profile),
+                }
+                .into());
+            }
         }
+        Ok(())
     }
     async fn apply(&self) -> Result<(), Box<dyn Error + 'static>> {
-        match &self.theme_id {
-            Some(theme) => {
-                let mut url = self.get_endpoint()?.join("/set_theme")?;
-                url.set_query(Some(&("id=".to_owned() + theme)));
-                reqwest::get(url).await?;
-                Ok(())
+        let mut failed = vec![];
+        for instance in &self.instances {
+            let Some(theme_id) = self.theme_ids.get(&instance.profile) else {
+                continue;
+            };
+            if let Err(e) = Pprefox::set_theme(&instance.endpoint, theme_id).await {
+                failed.push(format!("{} ({})", instance.profile, e));
+            }
+        }
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            Err(PprefoxError::ProfilesFailed {
+                profiles: failed.join(", "),
             }
-            None => Ok(()),
+            .into())
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instance(profile: &str, port: u16) -> Instance {
+        Instance {
+            profile: profile.to_string(),
+            endpoint: Url::from_str(&format!("http://127.0.0.1:{}/", port)).unwrap(),
+        }
+    }
+
+    #[test]
+    fn instances_file() {
+        let instances = Pprefox::parse_instances_file(
+            r#"
+            [profiles]
+            work = "http://127.0.0.1:8081/"
+            default = "http://127.0.0.1:8080/"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            instances,
+            [instance("default", 8080), instance("work", 8081)]
+        );
+        assert!(Pprefox::parse_instances_file("").is_err());
+        assert!(matches!(
+            Pprefox::parse_instances_file("[profiles]\nwork = \"not a url\"\n"),
+            Err(PprefoxError::InvalidInstancesFile { .. })
+        ));
+    }
+
+    #[test]
+    fn select_by_profile_name() {
+        let instances = vec![
+            instance("default", 8080),
+            instance("Work", 8081),
+            instance("127.0.0.1:8082", 8082),
+        ];
+        assert_eq!(
+            Pprefox::select(instances.clone(), &None).unwrap(),
+            instances
+        );
+        assert_eq!(
+            Pprefox::select(
+                instances.clone(),
+                &Some(vec!["work".to_string(), "127.0.0.1:8082".to_string()])
+            )
+            .unwrap(),
+            [instance("Work", 8081), instance("127.0.0.1:8082", 8082)]
+        );
+        assert!(matches!(
+            Pprefox::select(instances, &Some(vec!["missing".to_string()])),
+            Err(PprefoxError::ProfileNotFound { name }) if name == "missing"
+        ));
+    }
+}
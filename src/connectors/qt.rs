@@ -0,0 +1,314 @@
+/*
+  Qt connector
+  Name: qt
+  Controls: Qt 5/6 style, icon theme, color scheme, Kvantum theme
+
+  Config options:
+  - Style: Option<String> - Qt style, such as `Fusion` or `kvantum`. None = do not change, unless a Kvantum theme is set
+  - Icon theme: Option<String> - Name of an installed icon theme. None = do not change
+  - Color scheme: Option<String> - Name of a qt5ct/qt6ct color scheme, without `.conf`. None = do not change
+  NOTE: The scheme has to be installed for every tool that has a config folder, or that tool's apps would
+  silently keep their colors.
+  - Kvantum theme: Option<String> - Name of an installed Kvantum theme. None = do not change
+  - Kvantum palette: Option<Palette> - Colors to override the Kvantum theme's with. None = do not change
+  NOTE: Overridden Kvantum themes are saved as `<theme>#`, the same way Kvantum Manager saves modified
+  themes. Kvantum keeps using the original theme's SVG for them.
+*/
+
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    edit::{self, ini},
+    palette::{Color, Palette},
+    xdg,
+};
+
+use super::Connector;
+
+custom_error::custom_error! {pub QtError
+    MissingHome = "could not find the home directory",
+    ColorSchemeNotFound{name: String} = "qt5ct/qt6ct color scheme not installed: {name}",
+    ColorSchemeNotFoundFor{name: String, qtct: String} = "{qtct} color scheme not installed: {name} (it is only installed for the other tool)",
+    IconThemeNotFound{name: String} = "icon theme not installed: {name}",
+    KvantumThemeNotFound{name: String} = "Kvantum theme not installed: {name}"
+}
+
+// The config tools for each Qt version. Both are written so Qt 5 and Qt 6 apps match.
+const QTCT: [&str; 2] = ["qt5ct", "qt6ct"];
+
+pub struct Qt {
+    pub style: Option<String>,
+    pub icon_theme: Option<String>,
+    pub color_scheme: Option<String>,
+    pub kvantum_theme: Option<String>,
+    pub kvantum_palette: Option<Palette>,
+}
+
+impl Qt {
+    fn config_dir() -> Result<PathBuf, QtError> {
+        dirs::config_dir().ok_or(QtError::MissingHome)
+    }
+    fn color_scheme_dirs(qtct: &str) -> Vec<PathBuf> {
+        let mut color_scheme_dirs = vec![];
+        if let Some(config) = dirs::config_dir() {
+            color_scheme_dirs.push(config.join(qtct).join("colors"));
+        }
+        color_scheme_dirs.extend(
+            xdg::data_dirs()
+                .into_iter()
+                .map(|data| data.join(qtct).join("colors")),
+        );
+        color_scheme_dirs
+    }
+    fn color_scheme_path(qtct: &str, name: &str) -> Option<PathBuf> {
+        Qt::color_scheme_dirs(qtct)
+            .into_iter()
+            .map(|dir| dir.join(format!("{}.conf", name)))
+            .find(|path| path.is_file())
+    }
+    /// The tools that have the color scheme `name` installed.
+    pub fn color_scheme_tools(name: &str) -> Vec<&'static str> {
+        QTCT.into_iter()
+            .filter(|qtct| Qt::color_scheme_path(qtct, name).is_some())
+            .collect()
+    }
+    // The scheme's file for each tool. Tools with a config folder are in use, so they must have it
+    fn color_scheme_paths(
+        config_dir: &Path,
+        name: &str,
+    ) -> Result<Vec<(&'static str, Option<PathBuf>)>, QtError> {
+        let paths = QTCT
+            .into_iter()
+            .map(|qtct| (qtct, Qt::color_scheme_path(qtct, name)))
+            .collect::<Vec<_>>();
+        if paths.iter().all(|(_, path)| path.is_none()) {
+            return Err(QtError::ColorSchemeNotFound {
+                name: name.to_string(),
+            });
+        }
+        if let Some((qtct, _)) = paths
+            .iter()
+            .find(|(qtct, path)| path.is_none() && config_dir.join(qtct).is_dir())
+        {
+            return Err(QtError::ColorSchemeNotFoundFor {
+                name: name.to_string(),
+                qtct: qtct.to_string(),
+            });
+        }
+        Ok(paths)
+    }
+    fn kvantum_dirs() -> Vec<PathBuf> {
+        let mut kvantum_dirs = vec![];
+        if let Some(config) = dirs::config_dir() {
+            kvantum_dirs.push(config.join("Kvantum"));
+        }
+        kvantum_dirs.extend(
+            xdg::data_dirs()
+                .into_iter()
+                .map(|data| data.join("Kvantum")),
+        );
+        kvantum_dirs
+    }
+    // A Kvantum theme is a folder holding `<name>.kvconfig`
+    fn kvantum_config_path(name: &str) -> Option<PathBuf> {
+        Qt::kvantum_dirs()
+            .into_iter()
+            .map(|dir| dir.join(name).join(format!("{}.kvconfig", name)))
+            .find(|path| path.is_file())
+    }
+    pub fn get_color_schemes() -> Vec<String> {
+        let mut schemes = vec![];
+        for qtct in QTCT {
+            for dir in Qt::color_scheme_dirs(qtct) {
+                let Ok(entries) = fs::read_dir(dir) else {
+                    continue;
+                };
+                for entry in entries.flatten() {
+                    let path = entry.path();
+                    if path.extension().is_some_and(|ext| ext == "conf") {
+                        if let Some(stem) = path.file_stem() {
+                            schemes.push(stem.to_string_lossy().to_string());
+                        }
+                    }
+                }
+            }
+        }
+        schemes.sort();
+        schemes.dedup();
+        schemes
+    }
+    pub fn get_kvantum_themes() -> Vec<String> {
+        let mut themes = vec![];
+        for dir in Qt::kvantum_dirs() {
+            let Ok(entries) = fs::read_dir(dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if Qt::kvantum_config_path(&name).is_some() {
+                    themes.push(name);
+                }
+            }
+        }
+        themes.sort();
+        themes.dedup();
+        themes
+    }
+    fn kvantum_colors(palette: &Palette) -> Vec<(&'static str, Color)> {
+        let surface = palette.surface();
+        vec![
+            ("window.color", palette.background),
+            ("base.color", palette.background),
+            ("alt.base.color", surface),
+            ("button.color", surface),
+            ("light.color", surface.mix(&palette.foreground, 0.1)),
+            ("mid.light.color", surface.mix(&palette.foreground, 0.05)),
+            ("dark.color", palette.background.mix(&Color::BLACK, 0.3)),
+            ("mid.color", palette.background.mix(&Color::BLACK, 0.15)),
+            ("highlight.color", palette.accent),
+            (
+                "inactive.highlight.color",
+                palette.accent.mix(&palette.background, 0.4),
+            ),
+            ("text.color", palette.foreground),
+            ("window.text.color", palette.foreground),
+            ("button.text.color", palette.foreground),
+            (
+                "disabled.text.color",
+                palette.foreground.mix(&palette.background, 0.5),
+            ),
+            ("tooltip.text.color", palette.foreground),
+            ("highlight.text.color", palette.accent_foreground()),
+            ("link.color", palette.accent),
+            (
+                "link.visited.color",
+                palette.accent.mix(&palette.foreground, 0.3),
+            ),
+        ]
+    }
+    // Writes `<theme>#` with the palette's colors and returns its name
+    fn write_kvantum_override(
+        config_dir: &Path,
+        theme: &str,
+        palette: &Palette,
+    ) -> Result<String, Box<dyn Error + 'static>> {
+        let base = theme.trim_end_matches('#');
+        let name = format!("{}#", base);
+        let base_config = Qt::kvantum_config_path(&name)
+            .or_else(|| Qt::kvantum_config_path(base))
+            .ok_or(QtError::KvantumThemeNotFound {
+                name: base.to_string(),
+            })?;
+        let mut contents = fs::read_to_string(base_config)?;
+        for (key, color) in Qt::kvantum_colors(palette) {
+            contents = ini::set(&contents, Some("GeneralColors"), key, &color.to_string());
+        }
+        let path = config_dir
+            .join("Kvantum")
+            .join(&name)
+            .join(format!("{}.kvconfig", name));
+        edit::file(&path, |_| contents)?;
+        Ok(name)
+    }
+}
+
+#[async_trait::async_trait]
+impl Connector for Qt {
+    fn new() -> Result<Self, Box<dyn Error + 'static>> {
+        Ok(Self {
+            style: None,
+            icon_theme: None,
+            color_scheme: None,
+            kvantum_theme: None,
+            kvantum_palette: None,
+        })
+    }
+    fn verify(&self) -> Result<(), Box<dyn Error + 'static>> {
+        let _ = Qt::config_dir()?;
+        if let Some(icon_theme) = &self.icon_theme {
+            if !xdg::icon_themes().contains(icon_theme) {
+                return Err(QtError::IconThemeNotFound {
+                    name: icon_theme.clone(),
+                }
+                .into());
+            }
+        }
+        if let Some(color_scheme) = &self.color_scheme {
+            Qt::color_scheme_paths(&Qt::config_dir()?, color_scheme)?;
+        }
+        if let Some(kvantum_theme) = &self.kvantum_theme {
+            if Qt::kvantum_config_path(kvantum_theme).is_none() {
+                return Err(QtError::KvantumThemeNotFound {
+                    name: kvantum_theme.clone(),
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+    async fn apply(&self) -> Result<(), Box<dyn Error + 'static>> {
+        let config_dir = Qt::config_dir()?;
+        let kvantum_config = config_dir.join("Kvantum").join("kvantum.kvconfig");
+        // Without a theme, the palette overrides whichever Kvantum theme is already selected
+        let kvantum_theme = self.kvantum_theme.clone().or_else(|| {
+            self.kvantum_palette.as_ref().and_then(|_| {
+                ini::get(
+                    &fs::read_to_string(&kvantum_config).unwrap_or_default(),
+                    Some("General"),
+                    "theme",
+                )
+            })
+        });
+        let kvantum_theme = match (&kvantum_theme, &self.kvantum_palette) {
+            (Some(theme), Some(palette)) => {
+                Some(Qt::write_kvantum_override(&config_dir, theme, palette)?)
+            }
+            _ => kvantum_theme,
+        };
+        if let Some(kvantum_theme) = &kvantum_theme {
+            edit::file(&kvantum_config, |contents| {
+                ini::set(contents, Some("General"), "theme", kvantum_theme)
+            })?;
+        }
+        // Kvantum themes only show up when the style is Kvantum
+        let style = self
+            .style
+            .clone()
+            .or_else(|| kvantum_theme.as_ref().map(|_| "kvantum".to_string()));
+        let color_scheme_paths = match &self.color_scheme {
+            Some(name) => Qt::color_scheme_paths(&config_dir, name)?,
+            None => QTCT.into_iter().map(|qtct| (qtct, None)).collect(),
+        };
+        for (qtct, color_scheme_path) in color_scheme_paths {
+            edit::file(
+                &config_dir.join(qtct).join(format!("{}.conf", qtct)),
+                |contents| {
+                    let mut contents = contents.to_string();
+                    if let Some(style) = &style {
+                        contents = ini::set(&contents, Some("Appearance"), "style", style);
+                    }
+                    if let Some(icon_theme) = &self.icon_theme {
+                        contents =
+                            ini::set(&contents, Some("Appearance"), "icon_theme", icon_theme);
+                    }
+                    if let Some(color_scheme_path) = &color_scheme_path {
+                        contents = ini::set(
+                            &contents,
+                            Some("Appearance"),
+                            "color_scheme_path",
+                            &color_scheme_path.to_string_lossy(),
+                        );
+                        contents =
+                            ini::set(&contents, Some("Appearance"), "custom_palette", "true");
+                    }
+                    contents
+                },
+            )?;
+        }
+        Ok(())
+    }
+}
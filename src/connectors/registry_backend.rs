@@ -0,0 +1,646 @@
+/*
+  Registry backends
+  Used by: windows
+
+  The Windows connector only talks to the registry through `RegistryBackend`, so its logic does
+  not depend on running on Windows. Keys are written with single backslashes, the same as regedit.
+  - WindowsRegistry: the real thing
+  - MemoryRegistry: values kept in memory, optionally on top of another backend
+  - RegFileWriter: turns writes into a .reg file for review instead of making them
+*/
+
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    sync::{Arc, Mutex},
+};
+
+custom_error::custom_error! {pub RegistryError
+    KeyNotFound{key: String} = "registry key not found: {key}"
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum RegHive {
+    CurrentUser,
+    LocalMachine,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegValue {
+    String(String),
+    ExpandString(String),
+    U32(u32),
+    Binary(Vec<u8>),
+}
+
+impl RegHive {
+    fn reg_file_name(&self) -> &'static str {
+        match self {
+            RegHive::CurrentUser => "HKEY_CURRENT_USER",
+            RegHive::LocalMachine => "HKEY_LOCAL_MACHINE",
+        }
+    }
+}
+
+impl From<bool> for RegValue {
+    fn from(value: bool) -> Self {
+        RegValue::U32(u32::from(value))
+    }
+}
+
+pub trait RegistryBackend: Send + Sync {
+    /// Names of the values directly under `key`.
+    fn value_names(
+        &self,
+        hive: RegHive,
+        key: &str,
+    ) -> Result<Vec<String>, Box<dyn Error + 'static>>;
+    /// `None` if the value does not exist.
+    fn get_value(
+        &self,
+        hive: RegHive,
+        key: &str,
+        name: &str,
+    ) -> Result<Option<RegValue>, Box<dyn Error + 'static>>;
+    /// Creates `key` if needed. An empty `name` is the key's `(Default)` value.
+    fn set_value(
+        &self,
+        hive: RegHive,
+        key: &str,
+        name: &str,
+        value: RegValue,
+    ) -> Result<(), Box<dyn Error + 'static>>;
+    /// Removes the value. One that, or whose key, does not exist is not an error.
+    fn delete_value(
+        &self,
+        hive: RegHive,
+        key: &str,
+        name: &str,
+    ) -> Result<(), Box<dyn Error + 'static>>;
+    /// Whether this is the registry Windows itself reads. Connectors skip side effects that
+    /// only make sense after a real change (running programs, refreshing settings) when it is not.
+    fn is_system(&self) -> bool {
+        false
+    }
+}
+
+impl<T: RegistryBackend + ?Sized> RegistryBackend for Arc<T> {
+    fn value_names(
+        &self,
+        hive: RegHive,
+        key: &str,
+    ) -> Result<Vec<String>, Box<dyn Error + 'static>> {
+        (**self).value_names(hive, key)
+    }
+    fn get_value(
+        &self,
+        hive: RegHive,
+        key: &str,
+        name: &str,
+    ) -> Result<Option<RegValue>, Box<dyn Error + 'static>> {
+        (**self).get_value(hive, key, name)
+    }
+    fn set_value(
+        &self,
+        hive: RegHive,
+        key: &str,
+        name: &str,
+        value: RegValue,
+    ) -> Result<(), Box<dyn Error + 'static>> {
+        (**self).set_value(hive, key, name, value)
+    }
+    fn delete_value(
+        &self,
+        hive: RegHive,
+        key: &str,
+        name: &str,
+    ) -> Result<(), Box<dyn Error + 'static>> {
+        (**self).delete_value(hive, key, name)
+    }
+    fn is_system(&self) -> bool {
+        (**self).is_system()
+    }
+}
+
+/// A key's path as first written and its values, as returned by `MemoryRegistry::written`.
+/// Deleted values are `None`.
+pub type WrittenKey = (RegHive, String, Vec<(String, Option<RegValue>)>);
+
+struct MemoryKey {
+    // As first written, since lookups ignore case
+    path: String,
+    // In the order they were first written. `None` once deleted, so the base's value is hidden too
+    values: Vec<(String, Option<RegValue>)>,
+}
+
+/// Keeps values in memory. Reads fall through to `base` for anything not written here, and
+/// writes never reach it.
+#[derive(Default)]
+pub struct MemoryRegistry {
+    base: Option<Box<dyn RegistryBackend>>,
+    keys: Mutex<BTreeMap<(RegHive, String), MemoryKey>>,
+}
+
+impl MemoryRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn over(base: Box<dyn RegistryBackend>) -> Self {
+        Self {
+            base: Some(base),
+            keys: Mutex::default(),
+        }
+    }
+    fn index(hive: RegHive, key: &str) -> (RegHive, String) {
+        (hive, key.to_lowercase())
+    }
+    /// Everything written so far, grouped by key, in a stable order.
+    pub fn written(&self) -> Vec<WrittenKey> {
+        self.keys
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|((hive, _), key)| (*hive, key.path.clone(), key.values.clone()))
+            .collect()
+    }
+    fn write(&self, hive: RegHive, key: &str, name: &str, value: Option<RegValue>) {
+        let mut keys = self.keys.lock().unwrap();
+        let memory_key = keys
+            .entry(MemoryRegistry::index(hive, key))
+            .or_insert_with(|| MemoryKey {
+                path: key.to_string(),
+                values: vec![],
+            });
+        match memory_key
+            .values
+            .iter_mut()
+            .find(|(existing, _)| existing.eq_ignore_ascii_case(name))
+        {
+            Some((_, existing)) => *existing = value,
+            None => memory_key.values.push((name.to_string(), value)),
+        }
+    }
+}
+
+impl RegistryBackend for MemoryRegistry {
+    fn value_names(
+        &self,
+        hive: RegHive,
+        key: &str,
+    ) -> Result<Vec<String>, Box<dyn Error + 'static>> {
+        let mut names = self
+            .base
+            .as_ref()
+            .and_then(|base| base.value_names(hive, key).ok());
+        if let Some(memory_key) = self
+            .keys
+            .lock()
+            .unwrap()
+            .get(&MemoryRegistry::index(hive, key))
+        {
+            let names = names.get_or_insert_with(Vec::new);
+            for (name, value) in &memory_key.values {
+                let position = names
+                    .iter()
+                    .position(|existing| existing.eq_ignore_ascii_case(name));
+                match (position, value) {
+                    (None, Some(_)) => names.push(name.clone()),
+                    (Some(position), None) => {
+                        names.remove(position);
+                    }
+                    _ => (),
+                }
+            }
+        }
+        names.ok_or_else(|| {
+            RegistryError::KeyNotFound {
+                key: key.to_string(),
+            }
+            .into()
+        })
+    }
+    fn get_value(
+        &self,
+        hive: RegHive,
+        key: &str,
+        name: &str,
+    ) -> Result<Option<RegValue>, Box<dyn Error + 'static>> {
+        if let Some(memory_key) = self
+            .keys
+            .lock()
+            .unwrap()
+            .get(&MemoryRegistry::index(hive, key))
+        {
+            if let Some((_, value)) = memory_key
+                .values
+                .iter()
+                .find(|(existing, _)| existing.eq_ignore_ascii_case(name))
+            {
+                return Ok(value.clone());
+            }
+        }
+        match &self.base {
+            Some(base) => base.get_value(hive, key, name),
+            None => Ok(None),
+        }
+    }
+    fn set_value(
+        &self,
+        hive: RegHive,
+        key: &str,
+        name: &str,
+        value: RegValue,
+    ) -> Result<(), Box<dyn Error + 'static>> {
+        self.write(hive, key, name, Some(value));
+        Ok(())
+    }
+    fn delete_value(
+        &self,
+        hive: RegHive,
+        key: &str,
+        name: &str,
+    ) -> Result<(), Box<dyn Error + 'static>> {
+        self.write(hive, key, name, None);
+        Ok(())
+    }
+}
+
+/// Collects writes into a `.reg` file instead of making them, so they can be reviewed and
+/// imported by hand. Reads still come from `base`, if there is one.
+pub struct RegFileWriter {
+    changes: MemoryRegistry,
+}
+
+impl RegFileWriter {
+    pub fn new(base: Option<Box<dyn RegistryBackend>>) -> Self {
+        Self {
+            changes: match base {
+                Some(base) => MemoryRegistry::over(base),
+                None => MemoryRegistry::new(),
+            },
+        }
+    }
+    fn escape(value: &str) -> String {
+        value.replace('\\', "\\\\").replace('"', "\\\"")
+    }
+    fn hex(bytes: &[u8]) -> String {
+        bytes
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+    fn data(value: &RegValue) -> String {
+        match value {
+            RegValue::String(data) => format!("\"{}\"", RegFileWriter::escape(data)),
+            RegValue::U32(data) => format!("dword:{:08x}", data),
+            RegValue::Binary(data) => format!("hex:{}", RegFileWriter::hex(data)),
+            // REG_EXPAND_SZ has no text form, only its null-terminated UTF-16 bytes
+            RegValue::ExpandString(data) => {
+                let bytes = data
+                    .encode_utf16()
+                    .chain([0])
+                    .flat_map(u16::to_le_bytes)
+                    .collect::<Vec<_>>();
+                format!("hex(2):{}", RegFileWriter::hex(&bytes))
+            }
+        }
+    }
+    /// The `.reg` file as text.
+    pub fn contents(&self) -> String {
+        let mut contents = String::from("Windows Registry Editor Version 5.00\r\n");
+        for (hive, key, values) in self.changes.written() {
+            contents.push_str(&format!("\r\n[{}\\{}]\r\n", hive.reg_file_name(), key));
+            for (name, value) in values {
+                let name = if name.is_empty() {
+                    "@".to_string()
+                } else {
+                    format!("\"{}\"", RegFileWriter::escape(&name))
+                };
+                // `-` deletes the value when the file is imported
+                let data = match &value {
+                    Some(value) => RegFileWriter::data(value),
+                    None => "-".to_string(),
+                };
+                contents.push_str(&format!("{}={}\r\n", name, data));
+            }
+        }
+        contents
+    }
+    /// The `.reg` file encoded the way regedit expects, UTF-16 with a byte order mark.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [0xFEFF]
+            .into_iter()
+            .chain(self.contents().encode_utf16())
+            .flat_map(u16::to_le_bytes)
+            .collect()
+    }
+}
+
+impl RegistryBackend for RegFileWriter {
+    fn value_names(
+        &self,
+        hive: RegHive,
+        key: &str,
+    ) -> Result<Vec<String>, Box<dyn Error + 'static>> {
+        self.changes.value_names(hive, key)
+    }
+    fn get_value(
+        &self,
+        hive: RegHive,
+        key: &str,
+        name: &str,
+    ) -> Result<Option<RegValue>, Box<dyn Error + 'static>> {
+        self.changes.get_value(hive, key, name)
+    }
+    fn set_value(
+        &self,
+        hive: RegHive,
+        key: &str,
+        name: &str,
+        value: RegValue,
+    ) -> Result<(), Box<dyn Error + 'static>> {
+        self.changes.set_value(hive, key, name, value)
+    }
+    fn delete_value(
+        &self,
+        hive: RegHive,
+        key: &str,
+        name: &str,
+    ) -> Result<(), Box<dyn Error + 'static>> {
+        self.changes.delete_value(hive, key, name)
+    }
+}
+
+/// The real registry, where there is one.
+#[cfg(windows)]
+pub fn system_registry() -> Option<Box<dyn RegistryBackend>> {
+    Some(Box::new(WindowsRegistry))
+}
+
+#[cfg(not(windows))]
+pub fn system_registry() -> Option<Box<dyn RegistryBackend>> {
+    None
+}
+
+#[cfg(windows)]
+pub struct WindowsRegistry;
+
+#[cfg(windows)]
+impl WindowsRegistry {
+    fn hive(hive: RegHive) -> registry::Hive {
+        match hive {
+            RegHive::CurrentUser => registry::Hive::CurrentUser,
+            RegHive::LocalMachine => registry::Hive::LocalMachine,
+        }
+    }
+}
+
+#[cfg(windows)]
+impl RegistryBackend for WindowsRegistry {
+    fn value_names(
+        &self,
+        hive: RegHive,
+        key: &str,
+    ) -> Result<Vec<String>, Box<dyn Error + 'static>> {
+        let key = WindowsRegistry::hive(hive).open(key, registry::Security::Read)?;
+        let mut names = vec![];
+        for value in key.values() {
+            names.push(value?.into_name().to_string()?);
+        }
+        Ok(names)
+    }
+    fn get_value(
+        &self,
+        hive: RegHive,
+        key: &str,
+        name: &str,
+    ) -> Result<Option<RegValue>, Box<dyn Error + 'static>> {
+        use registry::Data;
+        let key = WindowsRegistry::hive(hive).open(key, registry::Security::Read)?;
+        // A missing value is not an error here, the caller decides what it means
+        let Ok(data) = key.value(name) else {
+            return Ok(None);
+        };
+        Ok(match data {
+            Data::String(data) => Some(RegValue::String(data.to_string()?)),
+            Data::ExpandString(data) => Some(RegValue::ExpandString(data.to_string()?)),
+            Data::U32(data) => Some(RegValue::U32(data)),
+            Data::Binary(data) => Some(RegValue::Binary(data)),
+            _ => None,
+        })
+    }
+    fn set_value(
+        &self,
+        hive: RegHive,
+        key: &str,
+        name: &str,
+        value: RegValue,
+    ) -> Result<(), Box<dyn Error + 'static>> {
+        use registry::Data;
+        use utfx::U16CString;
+        let data = match value {
+            RegValue::String(data) => Data::String(U16CString::from_str(&data)?),
+            RegValue::ExpandString(data) => Data::ExpandString(U16CString::from_str(&data)?),
+            RegValue::U32(data) => Data::U32(data),
+            RegValue::Binary(data) => Data::Binary(data),
+        };
+        WindowsRegistry::hive(hive)
+            .create(key, registry::Security::Read | registry::Security::Write)?
+            .set_value(name, &data)?;
+        Ok(())
+    }
+    fn delete_value(
+        &self,
+        hive: RegHive,
+        key: &str,
+        name: &str,
+    ) -> Result<(), Box<dyn Error + 'static>> {
+        let key = match WindowsRegistry::hive(hive).open(key, registry::Security::Write) {
+            Ok(key) => key,
+            Err(registry::key::Error::NotFound(..)) => return Ok(()),
+            Err(error) => return Err(error.into()),
+        };
+        match key.delete_value(name) {
+            Ok(()) | Err(registry::value::Error::NotFound(..)) => Ok(()),
+            Err(error) => Err(error.into()),
+        }
+    }
+    fn is_system(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = r"Control Panel\Cursors";
+
+    fn string(value: &str) -> RegValue {
+        RegValue::String(value.to_string())
+    }
+
+    #[test]
+    fn memory_values_ignore_case() {
+        let registry = MemoryRegistry::new();
+        registry
+            .set_value(RegHive::CurrentUser, KEY, "Arrow", string("a.cur"))
+            .unwrap();
+        registry
+            .set_value(
+                RegHive::CurrentUser,
+                &KEY.to_uppercase(),
+                "ARROW",
+                string("b.cur"),
+            )
+            .unwrap();
+        assert_eq!(
+            registry
+                .get_value(RegHive::CurrentUser, KEY, "arrow")
+                .unwrap(),
+            Some(string("b.cur"))
+        );
+        assert_eq!(
+            registry
+                .get_value(RegHive::LocalMachine, KEY, "Arrow")
+                .unwrap(),
+            None
+        );
+        // The first spelling of the key and value is kept
+        assert_eq!(
+            registry.written(),
+            vec![(
+                RegHive::CurrentUser,
+                KEY.to_string(),
+                vec![("Arrow".to_string(), Some(string("b.cur")))]
+            )]
+        );
+        assert!(registry.value_names(RegHive::LocalMachine, KEY).is_err());
+    }
+
+    #[test]
+    fn memory_reads_through_but_never_writes_to_base() {
+        let base = Arc::new(MemoryRegistry::new());
+        base.set_value(RegHive::CurrentUser, KEY, "Arrow", string("base.cur"))
+            .unwrap();
+        base.set_value(RegHive::CurrentUser, KEY, "Hand", string("hand.cur"))
+            .unwrap();
+        let registry = MemoryRegistry::over(Box::new(base.clone()));
+        registry
+            .set_value(RegHive::CurrentUser, KEY, "Arrow", string("new.cur"))
+            .unwrap();
+        registry
+            .set_value(RegHive::CurrentUser, KEY, "IBeam", string("text.cur"))
+            .unwrap();
+        assert_eq!(
+            registry
+                .get_value(RegHive::CurrentUser, KEY, "Arrow")
+                .unwrap(),
+            Some(string("new.cur"))
+        );
+        assert_eq!(
+            registry
+                .get_value(RegHive::CurrentUser, KEY, "Hand")
+                .unwrap(),
+            Some(string("hand.cur"))
+        );
+        assert_eq!(
+            registry.value_names(RegHive::CurrentUser, KEY).unwrap(),
+            ["Arrow", "Hand", "IBeam"]
+        );
+        assert_eq!(
+            base.get_value(RegHive::CurrentUser, KEY, "Arrow").unwrap(),
+            Some(string("base.cur"))
+        );
+        assert_eq!(
+            base.get_value(RegHive::CurrentUser, KEY, "IBeam").unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn memory_deletes_hide_base_values() {
+        let base = Arc::new(MemoryRegistry::new());
+        base.set_value(RegHive::CurrentUser, KEY, "Arrow", string("base.cur"))
+            .unwrap();
+        base.set_value(RegHive::CurrentUser, KEY, "Hand", string("hand.cur"))
+            .unwrap();
+        let registry = MemoryRegistry::over(Box::new(base.clone()));
+        registry
+            .delete_value(RegHive::CurrentUser, KEY, "arrow")
+            .unwrap();
+        registry
+            .delete_value(RegHive::CurrentUser, KEY, "Missing")
+            .unwrap();
+        assert_eq!(
+            registry
+                .get_value(RegHive::CurrentUser, KEY, "Arrow")
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            registry.value_names(RegHive::CurrentUser, KEY).unwrap(),
+            ["Hand"]
+        );
+        assert_eq!(
+            base.get_value(RegHive::CurrentUser, KEY, "Arrow").unwrap(),
+            Some(string("base.cur"))
+        );
+        // Setting it again brings it back
+        registry
+            .set_value(RegHive::CurrentUser, KEY, "Arrow", string("new.cur"))
+            .unwrap();
+        assert_eq!(
+            registry.value_names(RegHive::CurrentUser, KEY).unwrap(),
+            ["Arrow", "Hand"]
+        );
+    }
+
+    #[test]
+    fn reg_file_contents() {
+        let writer = RegFileWriter::new(None);
+        writer
+            .set_value(RegHive::CurrentUser, KEY, "", string(r#"Miku "v2""#))
+            .unwrap();
+        writer
+            .set_value(RegHive::CurrentUser, KEY, "Scheme Source", RegValue::U32(1))
+            .unwrap();
+        writer
+            .set_value(
+                RegHive::CurrentUser,
+                KEY,
+                "Arrow",
+                RegValue::ExpandString("%A%".to_string()),
+            )
+            .unwrap();
+        writer
+            .set_value(
+                RegHive::LocalMachine,
+                r"Software\Test",
+                "Palette",
+                RegValue::Binary(vec![0x00, 0xab, 0xff]),
+            )
+            .unwrap();
+        writer
+            .delete_value(RegHive::LocalMachine, r"Software\Test", "Old")
+            .unwrap();
+        assert_eq!(
+            writer.contents(),
+            "Windows Registry Editor Version 5.00\r\n\
+             \r\n[HKEY_CURRENT_USER\\Control Panel\\Cursors]\r\n\
+             @=\"Miku \\\"v2\\\"\"\r\n\
+             \"Scheme Source\"=dword:00000001\r\n\
+             \"Arrow\"=hex(2):25,00,41,00,25,00,00,00\r\n\
+             \r\n[HKEY_LOCAL_MACHINE\\Software\\Test]\r\n\
+             \"Palette\"=hex:00,ab,ff\r\n\
+             \"Old\"=-\r\n"
+        );
+        let bytes = writer.to_bytes();
+        assert_eq!(bytes[..4], [0xff, 0xfe, b'W', 0x00]);
+        assert_eq!(
+            bytes.len(),
+            2 + writer.contents().encode_utf16().count() * 2
+        );
+    }
+}
@@ -0,0 +1,198 @@
+/*
+  Spicetify connector
+  Name: spicetify
+  Controls: Spotify theme and color scheme
+
+  Config options:
+  - Theme: Option<String> - Name of a theme in spicetify's Themes folder. None = do not change
+  - Color scheme: Option<String> - Name of a section in the theme's color.ini. None = do not change
+  - Palette: Option<Palette> - Colors for a `univeme` color scheme, added to the theme's color.ini and selected. None = do not change
+  - Run apply: bool - Whether to run `spicetify apply` afterwards, so Spotify picks up the changes.
+  NOTE: Only the config is edited. Spotify keeps showing the old theme until `spicetify apply` runs.
+*/
+
+use std::{error::Error, fs, path::PathBuf};
+
+use crate::{
+    edit::{self, ini},
+    palette::{Color, Palette},
+    process,
+};
+
+use super::Connector;
+
+custom_error::custom_error! {pub SpicetifyError
+    MissingConfig = "could not find spicetify's config-xpui.ini, run `spicetify` once first",
+    ThemeNotFound{name: String} = "spicetify theme not installed: {name}",
+    ColorSchemeNotFound{theme: String, name: String} = "color scheme {name} not found in spicetify theme {theme}",
+    MissingTheme = "a color scheme needs a theme, set `theme` or select one in spicetify first",
+    ApplyFailed = "`spicetify apply` failed"
+}
+
+/// Name of the color scheme made from the palette.
+pub const PALETTE_SCHEME: &str = "univeme";
+
+pub struct Spicetify {
+    pub config_dir: Option<PathBuf>,
+    pub theme: Option<String>,
+    pub color_scheme: Option<String>,
+    pub palette: Option<Palette>,
+    pub run_apply: bool,
+}
+
+impl Spicetify {
+    fn find_config_dir() -> Option<PathBuf> {
+        // spicetify uses ~/.config on macOS too, instead of Application Support
+        let mut candidates = vec![];
+        if let Some(config) = dirs::config_dir() {
+            candidates.push(config.join("spicetify"));
+        }
+        if let Some(home) = dirs::home_dir() {
+            candidates.push(home.join(".config").join("spicetify"));
+            candidates.push(home.join(".spicetify"));
+        }
+        candidates
+            .into_iter()
+            .find(|dir| dir.join("config-xpui.ini").exists())
+    }
+    fn config_dir(&self) -> Result<&PathBuf, SpicetifyError> {
+        self.config_dir
+            .as_ref()
+            .ok_or(SpicetifyError::MissingConfig)
+    }
+    fn config_file(&self) -> Result<PathBuf, SpicetifyError> {
+        Ok(self.config_dir()?.join("config-xpui.ini"))
+    }
+    pub fn get_themes(&self) -> Vec<String> {
+        let Ok(config_dir) = self.config_dir() else {
+            return vec![];
+        };
+        let Ok(entries) = fs::read_dir(config_dir.join("Themes")) else {
+            return vec![];
+        };
+        let mut themes = entries
+            .flatten()
+            .filter(|entry| entry.path().is_dir())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        themes.sort();
+        themes
+    }
+    /// The color schemes in a theme's color.ini.
+    pub fn get_color_schemes(&self, theme: &str) -> Vec<String> {
+        self.color_ini(theme)
+            .ok()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|contents| ini::sections(&contents))
+            .unwrap_or_default()
+    }
+    fn color_ini(&self, theme: &str) -> Result<PathBuf, SpicetifyError> {
+        Ok(self
+            .config_dir()?
+            .join("Themes")
+            .join(theme)
+            .join("color.ini"))
+    }
+    // The theme set here, or the one already selected in spicetify
+    fn target_theme(&self) -> Option<String> {
+        self.theme.clone().or_else(|| {
+            let contents = fs::read_to_string(self.config_file().ok()?).ok()?;
+            ini::get(&contents, Some("Setting"), "current_theme").filter(|theme| !theme.is_empty())
+        })
+    }
+    /// The keys of a spicetify color scheme, from the palette. Values have no leading `#`.
+    pub fn palette_scheme(palette: &Palette) -> Vec<(&'static str, String)> {
+        let background = palette.background;
+        let foreground = palette.foreground;
+        let surface = palette.surface();
+        let accent = palette.accent;
+        [
+            ("text", foreground),
+            ("subtext", foreground.mix(&background, 0.3)),
+            ("main", background),
+            ("sidebar", surface),
+            ("player", surface),
+            ("card", surface),
+            ("shadow", background.mix(&Color::BLACK, 0.5)),
+            ("selected-row", foreground.mix(&background, 0.2)),
+            ("button", accent),
+            ("button-active", accent.mix(&foreground, 0.2)),
+            ("button-disabled", foreground.mix(&background, 0.6)),
+            ("tab-active", surface.mix(&foreground, 0.1)),
+            ("notification", accent),
+            (
+                "notification-error",
+                palette.role("color1").unwrap_or(accent),
+            ),
+            ("misc", surface.mix(&foreground, 0.2)),
+        ]
+        .into_iter()
+        .map(|(key, color)| (key, color.hex_bare()))
+        .collect()
+    }
+}
+
+#[async_trait::async_trait]
+impl Connector for Spicetify {
+    fn new() -> Result<Self, Box<dyn Error + 'static>> {
+        Ok(Self {
+            config_dir: Spicetify::find_config_dir(),
+            theme: None,
+            color_scheme: None,
+            palette: None,
+            run_apply: false,
+        })
+    }
+    fn verify(&self) -> Result<(), Box<dyn Error + 'static>> {
+        let _ = self.config_dir()?;
+        if let Some(theme) = &self.theme {
+            if !self.get_themes().contains(theme) {
+                return Err(SpicetifyError::ThemeNotFound {
+                    name: theme.clone(),
+                }
+                .into());
+            }
+        }
+        if self.color_scheme.is_some() || self.palette.is_some() {
+            let theme = self.target_theme().ok_or(SpicetifyError::MissingTheme)?;
+            if let Some(color_scheme) = &self.color_scheme {
+                if !self.get_color_schemes(&theme).contains(color_scheme) {
+                    return Err(SpicetifyError::ColorSchemeNotFound {
+                        theme,
+                        name: color_scheme.clone(),
+                    }
+                    .into());
+                }
+            }
+        }
+        Ok(())
+    }
+    async fn apply(&self) -> Result<(), Box<dyn Error + 'static>> {
+        let mut color_scheme = self.color_scheme.clone();
+        if let Some(palette) = &self.palette {
+            let theme = self.target_theme().ok_or(SpicetifyError::MissingTheme)?;
+            edit::file(&self.color_ini(&theme)?, |contents| {
+                Spicetify::palette_scheme(palette)
+                    .iter()
+                    .fold(contents.to_string(), |contents, (key, value)| {
+                        ini::set(&contents, Some(PALETTE_SCHEME), key, value)
+                    })
+            })?;
+            color_scheme = Some(PALETTE_SCHEME.to_string());
+        }
+        edit::file(&self.config_file()?, |contents| {
+            let mut contents = contents.to_string();
+            if let Some(theme) = &self.theme {
+                contents = ini::set(&contents, Some("Setting"), "current_theme", theme);
+            }
+            if let Some(color_scheme) = &color_scheme {
+                contents = ini::set(&contents, Some("Setting"), "color_scheme", color_scheme);
+            }
+            contents
+        })?;
+        if self.run_apply && !process::run("spicetify", &["apply"]) {
+            return Err(SpicetifyError::ApplyFailed.into());
+        }
+        Ok(())
+    }
+}
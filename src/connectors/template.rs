@@ -0,0 +1,177 @@
+/*
+  Template connector
+  Name: template
+  Controls: any text file, rendered from a template
+
+  Config options:
+  - Template: PathBuf - File in Jinja syntax (rendered with minijinja) to render.
+  - Destination: PathBuf - Where the rendered file is written. Parent folders are created.
+  - Context: Value - What the template can use, see `context`.
+  - Post command: Option<String> - Shell command to run after writing, to reload the app. None = run nothing
+  NOTE: The destination is overwritten every time, so point it at a file the app includes, not one you edit.
+*/
+
+use std::{error::Error, fs, path::PathBuf, process::Command};
+
+use serde_json::{json, Map, Value};
+
+use crate::{edit, palette::Palette};
+
+use super::Connector;
+
+custom_error::custom_error! {pub TemplateError
+    TemplateNotFound{path: String} = "template not found: {path}",
+    PostCommandFailed{command: String} = "post_command failed: {command}"
+}
+
+pub struct Template {
+    pub template: PathBuf,
+    pub destination: PathBuf,
+    pub context: Value,
+    pub post_command: Option<String>,
+}
+
+/// What templates get to use: `name`, `author`, `light_mode`, `dark_mode` and `palette`, with
+/// every palette color as `#rrggbb` by the names presets use (`palette.accent`, `palette.color4`,
+/// ...). The `bare` filter drops the `#`.
+pub fn context(palette: &Option<Palette>, name: &Option<String>, author: &Option<String>) -> Value {
+    let mut colors = Map::new();
+    for (name, color) in palette.iter().flat_map(|palette| palette.named_colors()) {
+        colors.insert(name, Value::from(color.to_string()));
+    }
+    let light_mode = palette.as_ref().map(|palette| palette.is_light());
+    json!({
+        "name": name,
+        "author": author,
+        "light_mode": light_mode,
+        "dark_mode": light_mode.map(|light_mode| !light_mode),
+        "palette": colors,
+    })
+}
+
+/// Renders Jinja `source` with `context`.
+pub fn render(source: &str, context: &Value) -> Result<String, minijinja::Error> {
+    let mut environment = minijinja::Environment::new();
+    environment.set_keep_trailing_newline(true);
+    environment.add_filter("bare", |color: String| {
+        color.trim_start_matches('#').to_string()
+    });
+    environment.render_str(source, context)
+}
+
+impl Template {
+    fn source(&self) -> Result<String, TemplateError> {
+        fs::read_to_string(&self.template).map_err(|_| TemplateError::TemplateNotFound {
+            path: self.template.to_string_lossy().to_string(),
+        })
+    }
+    fn run_post_command(command: &str) -> Result<(), TemplateError> {
+        let status = if cfg!(windows) {
+            Command::new("cmd").args(["/C", command]).status()
+        } else {
+            Command::new("sh").args(["-c", command]).status()
+        };
+        if status.is_ok_and(|status| status.success()) {
+            Ok(())
+        } else {
+            Err(TemplateError::PostCommandFailed {
+                command: command.to_string(),
+            })
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Connector for Template {
+    fn new() -> Result<Self, Box<dyn Error + 'static>> {
+        Ok(Self {
+            template: PathBuf::new(),
+            destination: PathBuf::new(),
+            context: Value::Null,
+            post_command: None,
+        })
+    }
+    fn verify(&self) -> Result<(), Box<dyn Error + 'static>> {
+        // Rendering once here catches syntax errors and unknown filters before anything is applied
+        render(&self.source()?, &self.context)?;
+        Ok(())
+    }
+    async fn apply(&self) -> Result<(), Box<dyn Error + 'static>> {
+        let rendered = render(&self.source()?, &self.context)?;
+        edit::file(&self.destination, |_| rendered)?;
+        if let Some(post_command) = &self.post_command {
+            Template::run_post_command(post_command)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn palette() -> Palette {
+        toml::from_str(
+            r##"
+            background = "#101010"
+            foreground = "#f0f0f0"
+            accent = "#ff0080"
+            colors = ["#000000", "#ff0000"]
+            "##,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn palette_colors_and_bare_filter() {
+        let context = context(&Some(palette()), &Some("Miku".to_string()), &None);
+        assert_eq!(
+            render(
+                "{{ name }}: {{ palette.accent }} {{ palette.color1 | bare }}",
+                &context
+            )
+            .unwrap(),
+            "Miku: #ff0080 ff0000"
+        );
+        assert_eq!(
+            render("{{ dark_mode }} {{ light_mode }}", &context).unwrap(),
+            "True False"
+        );
+        // Only the terminal colors the palette has are there
+        assert_eq!(
+            render("{{ palette.color2 is defined }}", &context).unwrap(),
+            "False"
+        );
+    }
+
+    #[test]
+    fn keeps_the_trailing_newline() {
+        let context = context(&None, &None, &None);
+        assert_eq!(render("a\n", &context).unwrap(), "a\n");
+        assert_eq!(render("a\n\n", &context).unwrap(), "a\n\n");
+        assert_eq!(render("a", &context).unwrap(), "a");
+    }
+
+    #[test]
+    fn context_without_a_palette() {
+        let context = context(&None, &None, &Some("me".to_string()));
+        assert_eq!(context["light_mode"], Value::Null);
+        assert_eq!(context["dark_mode"], Value::Null);
+        assert_eq!(context["palette"], json!({}));
+        assert_eq!(
+            render(
+                "{% if dark_mode is none %}no mode{% endif %} by {{ author }}",
+                &context
+            )
+            .unwrap(),
+            "no mode by me"
+        );
+    }
+
+    #[test]
+    fn errors_are_returned() {
+        let context = context(&None, &None, &None);
+        assert!(render("{% if %}", &context).is_err());
+        assert!(render("{{ name | nope }}", &context).is_err());
+    }
+}
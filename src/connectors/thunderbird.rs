@@ -0,0 +1,143 @@
+/*
+  Thunderbird connector
+  Name: thunderbird
+  Controls: mail client colors, dark mode, theme
+
+  Config options:
+  - Profiles: Vec<Profile> - Thunderbird profiles to change, from `profiles.ini`.
+  - Palette: Option<Palette> - Colors for the folder pane, message list and toolbars, through userChrome.css. None = do not change
+  - Dark: Option<bool> - Whether Thunderbird and messages should use dark mode. None = do not change
+  - Theme ID: Option<String> - Add-on ID of an installed theme, like `thunderbird-compact-dark@mozilla.org`. None = do not change
+  NOTE: Thunderbird profiles work like Firefox's, see `mozilla`. The same restart and theme rules apply.
+*/
+
+use std::{error::Error, path::PathBuf};
+
+use serde_json::Value;
+
+use crate::{palette::Palette, process};
+
+use super::{
+    mozilla::{self, Profile, ProfileChanges},
+    Connector,
+};
+
+custom_error::custom_error! {pub ThunderbirdError
+    NoProfiles = "could not find any Thunderbird profiles"
+}
+
+pub struct Thunderbird {
+    pub profiles: Vec<Profile>,
+    pub palette: Option<Palette>,
+    pub dark: Option<bool>,
+    pub theme_id: Option<String>,
+}
+
+impl Thunderbird {
+    /// Folders with a `profiles.ini`, for every way Thunderbird is commonly installed.
+    fn roots() -> Vec<PathBuf> {
+        let mut roots = vec![];
+        #[cfg(windows)]
+        if let Some(app_data) = dirs::config_dir() {
+            roots.push(app_data.join("Thunderbird"));
+        }
+        #[cfg(target_os = "macos")]
+        if let Some(home) = dirs::home_dir() {
+            roots.push(home.join("Library").join("Thunderbird"));
+        }
+        #[cfg(all(unix, not(target_os = "macos")))]
+        if let Some(home) = dirs::home_dir() {
+            roots.push(home.join(".thunderbird"));
+            roots.push(home.join(".var/app/org.mozilla.Thunderbird/.thunderbird"));
+            roots.push(home.join("snap/thunderbird/common/.thunderbird"));
+        }
+        roots
+    }
+    pub fn get_profiles() -> Vec<Profile> {
+        mozilla::profiles(&Thunderbird::roots())
+    }
+    /// Whether files were changed that Thunderbird only reads when it starts, while it is running.
+    pub fn needs_restart(&self) -> bool {
+        (self.palette.is_some() || self.dark.is_some()) && Thunderbird::is_running()
+    }
+    pub fn is_running() -> bool {
+        process::is_running("thunderbird") || process::is_running("thunderbird-bin")
+    }
+    pub fn user_chrome(palette: &Palette) -> String {
+        let surface = palette.surface();
+        format!(
+            ":root {{
+  --lwt-accent-color: {background} !important;
+  --lwt-text-color: {foreground} !important;
+  --toolbar-bgcolor: {surface} !important;
+  --toolbar-color: {foreground} !important;
+  --toolbar-field-background-color: {background} !important;
+  --toolbar-field-color: {foreground} !important;
+  --toolbar-field-focus-border-color: {accent} !important;
+  --focus-outline-color: {accent} !important;
+  --sidebar-background-color: {surface} !important;
+  --sidebar-text-color: {foreground} !important;
+  --tree-view-bg: {background} !important;
+  --tree-view-color: {foreground} !important;
+  --tree-view-selected-bg: {accent} !important;
+  --tree-view-selected-color: {accent_foreground} !important;
+  --arrowpanel-background: {surface} !important;
+  --arrowpanel-color: {foreground} !important;
+  --selected-item-color: {accent} !important;
+  --selected-item-text-color: {accent_foreground} !important;
+}}
+",
+            background = palette.background,
+            foreground = palette.foreground,
+            accent = palette.accent,
+            accent_foreground = palette.accent_foreground(),
+            surface = surface,
+        )
+    }
+    fn prefs(&self) -> Vec<(&'static str, Value)> {
+        let mut prefs = mozilla::style_prefs(self.palette.is_some(), self.dark);
+        if let Some(dark) = self.dark {
+            // Message content has its own setting. 0 is dark and 1 is light
+            let theme = Value::from(if dark { 0 } else { 1 });
+            prefs.push(("browser.theme.content-theme", theme.clone()));
+            prefs.push(("browser.theme.toolbar-theme", theme));
+        }
+        prefs
+    }
+}
+
+#[async_trait::async_trait]
+impl Connector for Thunderbird {
+    fn new() -> Result<Self, Box<dyn Error + 'static>> {
+        Ok(Self {
+            profiles: Thunderbird::get_profiles(),
+            palette: None,
+            dark: None,
+            theme_id: None,
+        })
+    }
+    fn verify(&self) -> Result<(), Box<dyn Error + 'static>> {
+        if self.profiles.is_empty() {
+            return Err(ThunderbirdError::NoProfiles.into());
+        }
+        if let Some(theme_id) = &self.theme_id {
+            mozilla::verify_theme(&self.profiles, theme_id)?;
+        }
+        mozilla::verify_not_running("Thunderbird", Thunderbird::is_running(), &self.theme_id)?;
+        Ok(())
+    }
+    async fn apply(&self) -> Result<(), Box<dyn Error + 'static>> {
+        let mut chrome = vec![];
+        if let Some(palette) = &self.palette {
+            chrome.push(("userChrome.css", Thunderbird::user_chrome(palette)));
+        }
+        mozilla::apply(
+            &self.profiles,
+            &ProfileChanges {
+                prefs: self.prefs(),
+                chrome,
+                theme_id: self.theme_id.as_deref(),
+            },
+        )
+    }
+}
@@ -0,0 +1,192 @@
+/*
+  tmux connector
+  Name: tmux
+  Controls: status bar, pane border and message colors
+
+  Config options:
+  - Theme: Option<String> - Name of a theme file in `~/.config/tmux/themes`, without `.conf`. None = do not change
+  - Palette: Option<Palette> - Colors for a generated theme. Wins over the theme. None = do not change
+  NOTE: Either way the result goes to `~/.config/tmux/univeme.conf`, which tmux.conf sources from a managed
+  block. Every running tmux server sources it right away, so open sessions change too. Options a theme
+  does not set keep the value the previous one gave them until the server restarts.
+*/
+
+use std::{
+    error::Error,
+    fs,
+    os::unix::fs::FileTypeExt,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    edit::{self, block},
+    palette::Palette,
+    process, xdg,
+};
+
+use super::Connector;
+
+custom_error::custom_error! {pub TmuxError
+    MissingHome = "could not find the home folder",
+    ThemeNotFound{name: String} = "tmux theme not found: {name}"
+}
+
+pub struct Tmux {
+    pub theme: Option<String>,
+    pub palette: Option<Palette>,
+}
+
+impl Tmux {
+    fn config_dir() -> Result<PathBuf, TmuxError> {
+        Ok(xdg::config_home()
+            .ok_or(TmuxError::MissingHome)?
+            .join("tmux"))
+    }
+    // tmux reads ~/.tmux.conf first and only falls back to the XDG one without it
+    fn tmux_conf() -> Result<PathBuf, TmuxError> {
+        let home_conf = dirs::home_dir()
+            .ok_or(TmuxError::MissingHome)?
+            .join(".tmux.conf");
+        if home_conf.exists() {
+            return Ok(home_conf);
+        }
+        Ok(Tmux::config_dir()?.join("tmux.conf"))
+    }
+    fn theme_path(name: &str) -> Result<PathBuf, TmuxError> {
+        Ok(Tmux::config_dir()?
+            .join("themes")
+            .join(format!("{}.conf", name)))
+    }
+    pub fn get_themes() -> Vec<String> {
+        let Ok(config_dir) = Tmux::config_dir() else {
+            return vec![];
+        };
+        let Ok(entries) = fs::read_dir(config_dir.join("themes")) else {
+            return vec![];
+        };
+        entries
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.path();
+                if path.extension()? != "conf" {
+                    return None;
+                }
+                Some(path.file_stem()?.to_string_lossy().to_string())
+            })
+            .collect()
+    }
+    pub fn generate_theme(palette: &Palette) -> String {
+        let background = palette.background;
+        let foreground = palette.foreground;
+        let surface = palette.surface();
+        let accent = palette.accent;
+        let accent_foreground = palette.accent_foreground();
+        let muted = foreground.mix(&background, 0.4);
+        let border = surface.mix(&foreground, 0.2);
+        [
+            "# Generated by univeme from the preset palette".to_string(),
+            format!("set -g status-style \"bg={},fg={}\"", surface, foreground),
+            format!(
+                "set -g status-left-style \"bg={},fg={}\"",
+                accent, accent_foreground
+            ),
+            format!("set -g status-right-style \"bg={},fg={}\"", surface, muted),
+            format!("set -g window-status-style \"bg={},fg={}\"", surface, muted),
+            format!(
+                "set -g window-status-current-style \"bg={},fg={},bold\"",
+                accent, accent_foreground
+            ),
+            format!(
+                "set -g window-status-activity-style \"bg={},fg={}\"",
+                surface, accent
+            ),
+            format!("set -g pane-border-style \"fg={}\"", border),
+            format!("set -g pane-active-border-style \"fg={}\"", accent),
+            format!("set -g message-style \"bg={},fg={}\"", surface, foreground),
+            format!(
+                "set -g message-command-style \"bg={},fg={}\"",
+                surface, accent
+            ),
+            format!(
+                "set -g mode-style \"bg={},fg={}\"",
+                accent, accent_foreground
+            ),
+            format!("set -g display-panes-colour \"{}\"", muted),
+            format!("set -g display-panes-active-colour \"{}\"", accent),
+            format!("set -g clock-mode-colour \"{}\"", accent),
+        ]
+        .join("\n")
+            + "\n"
+    }
+    /// Sockets of the tmux servers this user runs, from `$TMUX_TMPDIR/tmux-<uid>`.
+    pub fn get_sockets() -> Vec<PathBuf> {
+        let Some(uid) = process::output("id", &["-u"]) else {
+            return vec![];
+        };
+        let tmp_dir = std::env::var_os("TMUX_TMPDIR")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("/tmp"));
+        let Ok(entries) = fs::read_dir(tmp_dir.join(format!("tmux-{}", uid.trim()))) else {
+            return vec![];
+        };
+        entries
+            .flatten()
+            .filter(|entry| {
+                entry
+                    .file_type()
+                    .is_ok_and(|file_type| file_type.is_socket())
+            })
+            .map(|entry| entry.path())
+            .collect()
+    }
+    fn source(theme: &Path) {
+        let theme = theme.to_string_lossy();
+        for socket in Tmux::get_sockets() {
+            let socket = socket.to_string_lossy();
+            // Sockets of servers that have exited stay behind, so failing here is expected
+            process::run("tmux", &["-S", &socket, "source-file", &theme]);
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Connector for Tmux {
+    fn new() -> Result<Self, Box<dyn Error + 'static>> {
+        Ok(Self {
+            theme: None,
+            palette: None,
+        })
+    }
+    fn verify(&self) -> Result<(), Box<dyn Error + 'static>> {
+        let _ = Tmux::config_dir()?;
+        if let (Some(theme), None) = (&self.theme, &self.palette) {
+            if !Tmux::theme_path(theme)?.is_file() {
+                return Err(TmuxError::ThemeNotFound {
+                    name: theme.clone(),
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+    async fn apply(&self) -> Result<(), Box<dyn Error + 'static>> {
+        let contents = match (&self.palette, &self.theme) {
+            (Some(palette), _) => Tmux::generate_theme(palette),
+            (None, Some(theme)) => fs::read_to_string(Tmux::theme_path(theme)?)?,
+            (None, None) => return Ok(()),
+        };
+        let theme_file = Tmux::config_dir()?.join("univeme.conf");
+        edit::file(&theme_file, |_| contents)?;
+        edit::file(&Tmux::tmux_conf()?, |tmux_conf| {
+            block::replace(
+                tmux_conf,
+                &block::HASH,
+                "theme",
+                &format!("source-file -q \"{}\"", theme_file.to_string_lossy()),
+            )
+        })?;
+        Tmux::source(&theme_file);
+        Ok(())
+    }
+}
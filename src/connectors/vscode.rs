@@ -0,0 +1,288 @@
+/*
+  VS Code connector
+  Name: vscode
+  Controls: editor theme, icon theme, font
+
+  Config options:
+  - Flavor: Flavor - Which build to edit. Code, Code - OSS, VSCodium or Insiders.
+  - Profile: Option<String> - Name of the profile to edit. None = default profile.
+  - Color theme: Option<String> - `workbench.colorTheme`. None = do not change
+  - Icon theme: Option<String> - `workbench.iconTheme`. None = do not change
+  - Product icon theme: Option<String> - `workbench.productIconTheme`. None = do not change
+  - Font family: Option<String> - `editor.fontFamily`. None = do not change
+  NOTE: settings.json is edited in place, so comments and formatting are kept.
+*/
+
+use serde::Deserialize;
+use std::{error::Error, fs, path::PathBuf, str::FromStr};
+
+use crate::edit::{self, jsonc};
+
+use super::Connector;
+
+custom_error::custom_error! {pub VscodeError
+    UnknownFlavor{name: String} = "unknown VS Code flavor: {name}",
+    MissingUserDir = "could not find the VS Code user settings directory",
+    ProfileNotFound{name: String} = "VS Code profile not found: {name}",
+    ThemeNotFound{name: String} = "VS Code theme not installed: {name}"
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Flavor {
+    Code,
+    CodeOss,
+    VSCodium,
+    Insiders,
+}
+
+impl FromStr for Flavor {
+    type Err = VscodeError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "code" => Ok(Flavor::Code),
+            "code-oss" | "code - oss" => Ok(Flavor::CodeOss),
+            "vscodium" | "codium" => Ok(Flavor::VSCodium),
+            "insiders" | "code-insiders" => Ok(Flavor::Insiders),
+            _ => Err(VscodeError::UnknownFlavor {
+                name: s.to_string(),
+            }),
+        }
+    }
+}
+
+impl Flavor {
+    // Folder name inside the OS config directory
+    fn config_name(&self) -> &'static str {
+        match self {
+            Flavor::Code => "Code",
+            Flavor::CodeOss => "Code - OSS",
+            Flavor::VSCodium => "VSCodium",
+            Flavor::Insiders => "Code - Insiders",
+        }
+    }
+    // Folder name inside the home directory where user extensions go
+    fn extensions_name(&self) -> &'static str {
+        match self {
+            Flavor::Code => ".vscode",
+            Flavor::CodeOss | Flavor::VSCodium => ".vscode-oss",
+            Flavor::Insiders => ".vscode-insiders",
+        }
+    }
+    // Places the built-in extensions (which include the default themes) are usually installed
+    fn builtin_extension_dirs(&self) -> Vec<PathBuf> {
+        let mut extension_dirs = vec![];
+        #[cfg(windows)]
+        if let Some(local) = dirs::data_local_dir() {
+            let program = match self {
+                Flavor::Code => "Microsoft VS Code",
+                Flavor::CodeOss => "Code - OSS",
+                Flavor::VSCodium => "VSCodium",
+                Flavor::Insiders => "Microsoft VS Code Insiders",
+            };
+            extension_dirs.push(
+                local
+                    .join("Programs")
+                    .join(program)
+                    .join(r"resources\app\extensions"),
+            );
+        }
+        #[cfg(not(windows))]
+        {
+            let installs: &[&str] = match self {
+                Flavor::Code => &[
+                    "/usr/share/code",
+                    "/opt/visual-studio-code",
+                    "/Applications/Visual Studio Code.app/Contents/Resources",
+                ],
+                Flavor::CodeOss => &["/usr/lib/code", "/usr/share/code-oss"],
+                Flavor::VSCodium => &[
+                    "/usr/share/codium",
+                    "/opt/vscodium-bin",
+                    "/Applications/VSCodium.app/Contents/Resources",
+                ],
+                Flavor::Insiders => &[
+                    "/usr/share/code-insiders",
+                    "/Applications/Visual Studio Code - Insiders.app/Contents/Resources",
+                ],
+            };
+            for install in installs {
+                extension_dirs.push(PathBuf::from(install).join("resources/app/extensions"));
+                extension_dirs.push(PathBuf::from(install).join("app/extensions"));
+                extension_dirs.push(PathBuf::from(install).join("extensions"));
+            }
+        }
+        extension_dirs
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct InstalledThemes {
+    pub color_themes: Vec<String>,
+    pub icon_themes: Vec<String>,
+    pub product_icon_themes: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ThemeContribution {
+    id: Option<String>,
+    label: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct Contributes {
+    #[serde(default)]
+    themes: Vec<ThemeContribution>,
+    #[serde(default)]
+    icon_themes: Vec<ThemeContribution>,
+    #[serde(default)]
+    product_icon_themes: Vec<ThemeContribution>,
+}
+
+#[derive(Deserialize)]
+struct PackageJson {
+    #[serde(default)]
+    contributes: Contributes,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UserDataProfile {
+    location: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StorageJson {
+    #[serde(default)]
+    user_data_profiles: Vec<UserDataProfile>,
+}
+
+pub struct Vscode {
+    pub flavor: Flavor,
+    pub profile: Option<String>,
+    pub color_theme: Option<String>,
+    pub icon_theme: Option<String>,
+    pub product_icon_theme: Option<String>,
+    pub font_family: Option<String>,
+}
+
+impl Vscode {
+    fn user_dir(&self) -> Result<PathBuf, VscodeError> {
+        dirs::config_dir()
+            .map(|config| config.join(self.flavor.config_name()).join("User"))
+            .filter(|dir| dir.is_dir())
+            .ok_or(VscodeError::MissingUserDir)
+    }
+    fn settings_path(&self) -> Result<PathBuf, Box<dyn Error + 'static>> {
+        let user_dir = self.user_dir()?;
+        match &self.profile {
+            None => Ok(user_dir.join("settings.json")),
+            Some(name) => {
+                let storage = user_dir.join("globalStorage").join("storage.json");
+                let profiles = match fs::read_to_string(storage) {
+                    Ok(contents) => serde_json::from_str::<StorageJson>(&contents)?,
+                    Err(_) => StorageJson {
+                        user_data_profiles: vec![],
+                    },
+                };
+                let profile = profiles
+                    .user_data_profiles
+                    .into_iter()
+                    .find(|profile| &profile.name == name)
+                    .ok_or(VscodeError::ProfileNotFound { name: name.clone() })?;
+                Ok(user_dir
+                    .join("profiles")
+                    .join(profile.location)
+                    .join("settings.json"))
+            }
+        }
+    }
+    pub fn get_installed_themes(&self) -> InstalledThemes {
+        let mut extension_dirs = self.flavor.builtin_extension_dirs();
+        if let Some(home) = dirs::home_dir() {
+            extension_dirs.push(home.join(self.flavor.extensions_name()).join("extensions"));
+        }
+        let mut installed = InstalledThemes::default();
+        // VS Code stores the `id` if there is one, otherwise the `label`
+        let names = |themes: Vec<ThemeContribution>| {
+            themes
+                .into_iter()
+                .filter_map(|theme| theme.id.or(theme.label))
+                .collect::<Vec<_>>()
+        };
+        for extension_dir in extension_dirs {
+            let Ok(extensions) = fs::read_dir(extension_dir) else {
+                continue;
+            };
+            for extension in extensions.flatten() {
+                let Ok(package) = fs::read_to_string(extension.path().join("package.json")) else {
+                    continue;
+                };
+                let Ok(package) = serde_json::from_str::<PackageJson>(&package) else {
+                    continue;
+                };
+                let contributes = package.contributes;
+                installed.color_themes.extend(names(contributes.themes));
+                installed.icon_themes.extend(names(contributes.icon_themes));
+                installed
+                    .product_icon_themes
+                    .extend(names(contributes.product_icon_themes));
+            }
+        }
+        installed
+    }
+}
+
+#[async_trait::async_trait]
+impl Connector for Vscode {
+    fn new() -> Result<Self, Box<dyn Error + 'static>> {
+        Ok(Self {
+            flavor: Flavor::Code,
+            profile: None,
+            color_theme: None,
+            icon_theme: None,
+            product_icon_theme: None,
+            font_family: None,
+        })
+    }
+    fn verify(&self) -> Result<(), Box<dyn Error + 'static>> {
+        let _ = self.settings_path()?;
+        let installed = self.get_installed_themes();
+        for (theme, available) in [
+            (&self.color_theme, &installed.color_themes),
+            (&self.icon_theme, &installed.icon_themes),
+            (&self.product_icon_theme, &installed.product_icon_themes),
+        ] {
+            if let Some(theme) = theme {
+                if !available.contains(theme) {
+                    return Err(VscodeError::ThemeNotFound {
+                        name: theme.clone(),
+                    }
+                    .into());
+                }
+            }
+        }
+        Ok(())
+    }
+    async fn apply(&self) -> Result<(), Box<dyn Error + 'static>> {
+        let settings_path = self.settings_path()?;
+        let mut settings = edit::read(&settings_path)?;
+        for (key, value) in [
+            ("workbench.colorTheme", &self.color_theme),
+            ("workbench.iconTheme", &self.icon_theme),
+            ("workbench.productIconTheme", &self.product_icon_theme),
+            ("editor.fontFamily", &self.font_family),
+        ] {
+            if let Some(value) = value {
+                settings = jsonc::set_top_level(&settings, key, &value.as_str().into())?;
+            }
+        }
+        if let Some(parent) = settings_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(settings_path, settings)?;
+        Ok(())
+    }
+}
@@ -10,40 +10,72 @@
   - Enable color prevalance: Option<bool> - Whether the taskbar, start, etc. should show the accent color. None = do not change
   - Light mode: Option<bool> - Whether Windows 11 apps should use their built in light modes. None = do not change
   NOTE: This will work best if you set all of your applications and websites to "System" theme (which copies the system's theme, set here).
+  - Accent color: Option<Color> - The accent color. Its lighter and darker shades are made from it. None = do not change
+  - Transparency: Option<bool> - Whether the taskbar, start, etc. are see-through. None = do not change
   - Cursor scheme: Option<CursorScheme> - Cursor scheme name/type.
+  - Cursor pack: Option<PathBuf> - A cursor pack folder or .zip with an install.inf. Installed as a user scheme before the cursor scheme is set. None = do not install
+  - UI font: Option<String> - Installed font family to use instead of Segoe UI, through the system font substitutes. None = do not change
+  NOTE: The UI font is a machine-wide setting, so it needs univeme to run as administrator. It shows after signing out.
+  Use "Segoe UI" to go back to the default. The Segoe UI font entries are saved under
+  HKLM\Software\univeme\Fonts before they are first emptied, and "Segoe UI" puts those values back and
+  removes the substitute again.
+  NOTE: All registry access goes through `registry`. When that is not the real registry (for example
+  when exporting a .reg file), the theme file is not run, cursor pack files are not copied and the
+  cursors are not refreshed.
 */
 
-use std::{error::Error, path::PathBuf, process::Command};
-
-use registry::*;
-use utfx::U16CString;
-use winsafe::co::{SPI, SPIF};
-
-use super::Connector;
-
-const CURSOR_ORDER: [&str; 15] = [
-    "Arrow",
-    "Help",
-    "AppStarting",
-    "Wait",
-    "Crosshair",
-    "IBeam",
-    "NWPen",
-    "No",
-    "SizeNS",
-    "SizeWE",
-    "SizeNWSE",
-    "SizeNESW",
-    "SizeAll",
-    "UpArrow",
-    "Hand",
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+use crate::palette::Color;
+
+use super::{
+    cursor_pack::{self, CURSOR_ORDER},
+    registry_backend::{self, RegHive, RegValue, RegistryBackend},
+    Connector,
+};
+
+/// Cursor scheme name that resets to the built-in cursors.
+pub const WINDOWS_DEFAULT_SCHEME: &str = "WindowsDefault";
+
+const PERSONALIZE_KEY: &str = r"Software\Microsoft\Windows\CurrentVersion\Themes\Personalize";
+const ACCENT_KEY: &str = r"Software\Microsoft\Windows\CurrentVersion\Explorer\Accent";
+const DWM_KEY: &str = r"Software\Microsoft\Windows\DWM";
+const CURSORS_KEY: &str = r"Control Panel\Cursors";
+const USER_SCHEMES_KEY: &str = r"Control Panel\Cursors\Schemes";
+const SYSTEM_SCHEMES_KEY: &str =
+    r"Software\Microsoft\Windows\CurrentVersion\Control Panel\Cursors\Schemes";
+const FONTS_KEY: &str = r"Software\Microsoft\Windows NT\CurrentVersion\Fonts";
+const FONT_SUBSTITUTES_KEY: &str = r"Software\Microsoft\Windows NT\CurrentVersion\FontSubstitutes";
+const FONTS_BACKUP_KEY: &str = r"Software\univeme\Fonts";
+
+/// Font Windows uses for its UI.
+pub const WINDOWS_UI_FONT: &str = "Segoe UI";
+
+// Substitutes are only used for fonts that are not installed, so these entries are emptied while
+// another font replaces Segoe UI. The files are the stock ones, only used when an entry can not be
+// read to save it. Segoe UI Symbol, Emoji and Variable are separate families and stay as they are
+const SEGOE_UI_FILES: [(&str, &str); 6] = [
+    ("Segoe UI (TrueType)", "segoeui.ttf"),
+    ("Segoe UI Bold (TrueType)", "segoeuib.ttf"),
+    ("Segoe UI Bold Italic (TrueType)", "segoeuiz.ttf"),
+    ("Segoe UI Italic (TrueType)", "segoeuii.ttf"),
+    ("Segoe UI Light (TrueType)", "segoeuil.ttf"),
+    ("Segoe UI Semibold (TrueType)", "seguisb.ttf"),
 ];
 
 pub struct Windows {
     pub theme_path: Option<PathBuf>,
     pub enable_color_prevalence: Option<bool>,
     pub light_mode: Option<bool>,
+    pub accent_color: Option<Color>,
+    pub transparency: Option<bool>,
     pub cursor_scheme: Option<CursorScheme>,
+    pub cursor_pack: Option<PathBuf>,
+    pub ui_font: Option<String>,
+    pub registry: Box<dyn RegistryBackend>,
 }
 
 #[derive(Debug, Clone)]
@@ -54,79 +86,291 @@ pub enum CursorScheme {
 }
 
 custom_error::custom_error! {pub WindowsError
-    SchemeNotFound = "cursor scheme not found"
+    SchemeNotFound{name: String} = "cursor scheme not found: {name}",
+    SchemeNeedsRegistry{name: String} = "cursor scheme {name} can only be looked up in the Windows registry, use WindowsDefault or a cursor_pack without one",
+    FontNotFound{name: String} = "font not installed: {name}",
+    MissingLocalData = "could not find the local app data folder",
+    Unsupported = "the Windows registry is only available on Windows"
 }
 
 impl Windows {
-    fn bool_to_data(input: bool) -> Data {
-        if input {
-            Data::U32(0x00000001)
-        } else {
-            Data::U32(0x00000000)
+    pub fn with_registry(registry: Box<dyn RegistryBackend>) -> Self {
+        Self {
+            theme_path: None,
+            enable_color_prevalence: None,
+            light_mode: None,
+            accent_color: None,
+            transparency: None,
+            cursor_scheme: None,
+            cursor_pack: None,
+            ui_font: None,
+            registry,
         }
     }
-    fn get_system_scheme_key() -> Result<RegKey, Box<dyn Error + 'static>> {
-        Ok(Hive::LocalMachine.open(
-            r"Software\\Microsoft\\Windows\\CurrentVersion\\Control Panel\\Cursors\\Schemes",
-            Security::Read,
-        )?)
+    pub fn get_system_cursor_schemes(&self) -> Result<Vec<String>, Box<dyn Error + 'static>> {
+        self.registry
+            .value_names(RegHive::LocalMachine, SYSTEM_SCHEMES_KEY)
     }
-    fn get_user_scheme_key() -> Result<RegKey, Box<dyn Error + 'static>> {
-        Ok(Hive::CurrentUser.open(r"Control Panel\\Cursors\\Schemes", Security::Read)?)
+    pub fn get_user_cursor_schemes(&self) -> Result<Vec<String>, Box<dyn Error + 'static>> {
+        self.registry
+            .value_names(RegHive::CurrentUser, USER_SCHEMES_KEY)
     }
-    pub fn get_system_cursor_schemes() -> Result<Vec<String>, Box<dyn Error + 'static>> {
-        let system_scheme_key = Windows::get_system_scheme_key()?;
-        let system_schemes = system_scheme_key
-            .values()
-            .map(|value_option| value_option.unwrap().into_name().to_string().unwrap())
+    /// Font families installed for everyone or just this user, from the names of their
+    /// registry entries, like `Cambria & Cambria Math (TrueType)`.
+    pub fn get_installed_fonts(&self) -> Vec<String> {
+        let mut fonts = [RegHive::LocalMachine, RegHive::CurrentUser]
+            .into_iter()
+            .flat_map(|hive| {
+                self.registry
+                    .value_names(hive, FONTS_KEY)
+                    .unwrap_or_default()
+            })
+            .flat_map(|name| {
+                let name = match name.rfind(" (") {
+                    Some(end) => name[..end].to_string(),
+                    None => name,
+                };
+                name.split(" & ")
+                    .map(|font| font.trim().to_string())
+                    .collect::<Vec<_>>()
+            })
             .collect::<Vec<_>>();
-        Ok(system_schemes)
+        fonts.sort();
+        fonts.dedup();
+        fonts
     }
-    pub fn get_user_cursor_schemes() -> Result<Vec<String>, Box<dyn Error + 'static>> {
-        let user_scheme_key = Windows::get_user_scheme_key()?;
-        let user_schemes = user_scheme_key
-            .values()
-            .map(|value_option| value_option.unwrap().into_name().to_string().unwrap())
-            .collect::<Vec<_>>();
-        Ok(user_schemes)
+    /// Whether `family` is installed. Entries are per style, so `Fira Sans Bold` counts for `Fira Sans`.
+    pub fn has_font(installed: &[String], family: &str) -> bool {
+        installed.iter().any(|font| {
+            font == family
+                || font
+                    .strip_prefix(family)
+                    .is_some_and(|style| style.starts_with(' '))
+        })
+    }
+    fn set_ui_font(&self, font: &str) -> Result<(), Box<dyn Error + 'static>> {
+        let machine = RegHive::LocalMachine;
+        for (name, file) in SEGOE_UI_FILES {
+            let original = match self.registry.get_value(machine, FONTS_BACKUP_KEY, name)? {
+                Some(original) => original,
+                None => {
+                    let file = match self.registry.get_value(machine, FONTS_KEY, name)? {
+                        Some(RegValue::String(file)) if !file.is_empty() => file,
+                        _ => file.to_string(),
+                    };
+                    let original = RegValue::String(file);
+                    self.registry
+                        .set_value(machine, FONTS_BACKUP_KEY, name, original.clone())?;
+                    original
+                }
+            };
+            let value = if font == WINDOWS_UI_FONT {
+                original
+            } else {
+                RegValue::String(String::new())
+            };
+            self.registry.set_value(machine, FONTS_KEY, name, value)?;
+        }
+        if font == WINDOWS_UI_FONT {
+            self.registry
+                .delete_value(machine, FONT_SUBSTITUTES_KEY, WINDOWS_UI_FONT)?;
+        } else {
+            self.registry.set_value(
+                machine,
+                FONT_SUBSTITUTES_KEY,
+                WINDOWS_UI_FONT,
+                RegValue::String(font.to_string()),
+            )?;
+        }
+        Ok(())
+    }
+    /// Copies a cursor pack's files and registers it as a user scheme, the same as
+    /// right-clicking its install.inf. Returns the scheme name. Files are only copied when
+    /// writing to the real registry.
+    pub fn install_cursor_pack(&self, pack: &Path) -> Result<String, Box<dyn Error + 'static>> {
+        let cursors_dir = dirs::data_local_dir()
+            .ok_or(WindowsError::MissingLocalData)?
+            .join("univeme")
+            .join("Cursors");
+        let (pack, install_dir) = if self.registry.is_system() {
+            cursor_pack::install_pack(pack, &cursors_dir)?
+        } else {
+            let pack = cursor_pack::read_pack(pack)?;
+            let install_dir = cursors_dir.join(&pack.scheme_name);
+            (pack, install_dir)
+        };
+        self.registry.set_value(
+            RegHive::CurrentUser,
+            USER_SCHEMES_KEY,
+            &pack.scheme_name,
+            RegValue::String(pack.scheme_value(&install_dir)),
+        )?;
+        Ok(pack.scheme_name)
+    }
+    // Windows stores colors as 0xAABBGGRR
+    fn abgr(color: &Color) -> u32 {
+        0xFF000000 | (u32::from(color.b) << 16) | (u32::from(color.g) << 8) | u32::from(color.r)
+    }
+    /// The 8 shades Windows keeps in `AccentPalette`, lightest first, as RGBA bytes.
+    pub fn accent_palette(accent: &Color) -> Vec<u8> {
+        [
+            accent.mix(&Color::WHITE, 0.6),
+            accent.mix(&Color::WHITE, 0.4),
+            accent.mix(&Color::WHITE, 0.2),
+            *accent,
+            accent.mix(&Color::BLACK, 0.2),
+            accent.mix(&Color::BLACK, 0.4),
+            accent.mix(&Color::BLACK, 0.6),
+            accent.mix(&Color::BLACK, 0.8),
+        ]
+        .iter()
+        .flat_map(|shade| [shade.r, shade.g, shade.b, 0x00])
+        .collect()
+    }
+    fn set_accent(&self, accent: &Color) -> Result<(), Box<dyn Error + 'static>> {
+        let user = RegHive::CurrentUser;
+        self.registry.set_value(
+            user,
+            ACCENT_KEY,
+            "AccentPalette",
+            RegValue::Binary(Windows::accent_palette(accent)),
+        )?;
+        self.registry.set_value(
+            user,
+            ACCENT_KEY,
+            "AccentColorMenu",
+            RegValue::U32(Windows::abgr(accent)),
+        )?;
+        self.registry.set_value(
+            user,
+            ACCENT_KEY,
+            "StartColorMenu",
+            RegValue::U32(Windows::abgr(&accent.mix(&Color::BLACK, 0.4))),
+        )?;
+        // Title bars and borders read the accent from DWM instead
+        self.registry.set_value(
+            user,
+            DWM_KEY,
+            "AccentColor",
+            RegValue::U32(Windows::abgr(accent)),
+        )?;
+        Ok(())
+    }
+    fn scheme_cursors(
+        &self,
+        hive: RegHive,
+        key: &str,
+        name: &str,
+    ) -> Result<Vec<String>, Box<dyn Error + 'static>> {
+        match self.registry.get_value(hive, key, name)? {
+            Some(RegValue::String(data) | RegValue::ExpandString(data)) => {
+                Ok(data.split(',').map(|f| f.to_string()).collect::<Vec<_>>())
+            }
+            _ => Err(WindowsError::SchemeNotFound {
+                name: name.to_string(),
+            }
+            .into()),
+        }
+    }
+    fn set_cursor_scheme(
+        &self,
+        cursor_scheme: &CursorScheme,
+    ) -> Result<(), Box<dyn Error + 'static>> {
+        let user = RegHive::CurrentUser;
+        // Scheme source info from https://thebitguru.com/articles/programmatically-changing-windows-mouse-cursors/3
+        let cursors = match cursor_scheme {
+            CursorScheme::WindowsDefault => {
+                // Scheme Source varies depending on where the cursor scheme comes from
+                self.registry
+                    .set_value(user, CURSORS_KEY, "Scheme Source", RegValue::U32(0))?;
+                // Windows Default has nothing/empty REG_SZ in (Default)
+                self.registry
+                    .set_value(user, CURSORS_KEY, "", RegValue::String(String::new()))?;
+                None
+            }
+            CursorScheme::UserScheme(name) => {
+                self.registry
+                    .set_value(user, CURSORS_KEY, "Scheme Source", RegValue::U32(1))?;
+                self.registry
+                    .set_value(user, CURSORS_KEY, "", RegValue::String(name.clone()))?;
+                Some(self.scheme_cursors(user, USER_SCHEMES_KEY, name)?)
+            }
+            CursorScheme::SystemScheme(name) => {
+                self.registry
+                    .set_value(user, CURSORS_KEY, "Scheme Source", RegValue::U32(2))?;
+                self.registry
+                    .set_value(user, CURSORS_KEY, "", RegValue::String(name.clone()))?;
+                Some(self.scheme_cursors(RegHive::LocalMachine, SYSTEM_SCHEMES_KEY, name)?)
+            }
+        };
+        let cursors = cursors.unwrap_or_default();
+        for (i, item) in CURSOR_ORDER.iter().enumerate() {
+            self.registry.set_value(
+                user,
+                CURSORS_KEY,
+                item,
+                // if the cursor is not specified, use default of blank which will use system default
+                RegValue::ExpandString(cursors.get(i).cloned().unwrap_or_default()),
+            )?;
+        }
+        Ok(())
     }
 }
 
 #[async_trait::async_trait]
 impl Connector for Windows {
     fn new() -> Result<Self, Box<dyn Error + 'static>> {
-        Ok(Self {
-            theme_path: None,
-            enable_color_prevalence: None,
-            light_mode: None,
-            cursor_scheme: None,
-        })
+        let registry = registry_backend::system_registry().ok_or(WindowsError::Unsupported)?;
+        Ok(Windows::with_registry(registry))
     }
     fn verify(&self) -> Result<(), Box<dyn Error + 'static>> {
         // Check if we have access to registry
-        let _ = Hive::CurrentUser.open(
-            r"Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize",
-            Security::Read | Security::Write,
-        )?;
+        let _ = self
+            .registry
+            .value_names(RegHive::CurrentUser, PERSONALIZE_KEY)?;
         Ok(())
     }
     async fn apply(&self) -> Result<(), Box<dyn Error + 'static>> {
-        let personalize = Hive::CurrentUser.open(
-            r"Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize",
-            Security::Read | Security::Write,
-        )?;
+        let user = RegHive::CurrentUser;
         if let Some(light_mode) = self.light_mode {
-            personalize.set_value("AppsUseLightTheme", &Windows::bool_to_data(light_mode))?;
-            personalize.set_value("SystemUsesLightTheme", &Windows::bool_to_data(light_mode))?;
+            self.registry.set_value(
+                user,
+                PERSONALIZE_KEY,
+                "AppsUseLightTheme",
+                light_mode.into(),
+            )?;
+            self.registry.set_value(
+                user,
+                PERSONALIZE_KEY,
+                "SystemUsesLightTheme",
+                light_mode.into(),
+            )?;
         }
         if let Some(enable_color_prevalance) = self.enable_color_prevalence {
-            personalize.set_value(
+            self.registry.set_value(
+                user,
+                PERSONALIZE_KEY,
                 "ColorPrevalence",
-                &Windows::bool_to_data(enable_color_prevalance),
+                enable_color_prevalance.into(),
             )?;
         }
-        //let accent = Hive::CurrentUser.open(r"Software\\Microsoft\\Windows\\CurrentVersion\\Explorer\\Accent", Security::Read | Security::Write)?;
-        if let Some(theme_path) = &self.theme_path {
+        if let Some(transparency) = self.transparency {
+            self.registry.set_value(
+                user,
+                PERSONALIZE_KEY,
+                "EnableTransparency",
+                transparency.into(),
+            )?;
+        }
+        if let Some(accent_color) = &self.accent_color {
+            self.set_accent(accent_color)?;
+        }
+        #[cfg(windows)]
+        if let Some(theme_path) = self
+            .theme_path
+            .as_ref()
+            .filter(|_| self.registry.is_system())
+        {
             if theme_path
                 .extension()
                 .map(|ext| ext.to_str().unwrap_or(""))
@@ -134,70 +378,185 @@ impl Connector for Windows {
                 == "theme"
             {
                 // Execute the theme file
-                Command::new("cmd")
+                std::process::Command::new("cmd")
                     .arg("/C")
                     .arg(theme_path.as_os_str())
                     .output()?;
             }
         }
+        if let Some(ui_font) = &self.ui_font {
+            self.set_ui_font(ui_font)?;
+        }
+        if let Some(cursor_pack) = &self.cursor_pack {
+            self.install_cursor_pack(cursor_pack)?;
+        }
         if let Some(cursor_scheme) = &self.cursor_scheme {
-            let cursor_key = Hive::CurrentUser
-                .open(r"Control Panel\\Cursors", Security::Read | Security::Write)?;
-            // Scheme source info from https://thebitguru.com/articles/programmatically-changing-windows-mouse-cursors/3
-            let cursors: Option<Vec<String>>;
-            match cursor_scheme {
-                CursorScheme::WindowsDefault => {
-                    // Scheme Source varies depending on where the cursor scheme comes from
-                    cursor_key.set_value("Scheme Source", &Data::U32(0))?;
-                    // Windows Default has nothing/empty REG_SZ in (Default)
-                    cursor_key.set_value("", &Data::String(U16CString::from_str("")?))?;
-                    cursors = None;
+            self.set_cursor_scheme(cursor_scheme)?;
+            #[cfg(windows)]
+            if self.registry.is_system() {
+                use winsafe::co::{SPI, SPIF};
+                unsafe {
+                    winsafe::SystemParametersInfo(SPI::SETCURSORS, 0, &mut 0, SPIF::NoValue)?;
                 }
-                CursorScheme::UserScheme(name) => {
-                    cursor_key.set_value("Scheme Source", &Data::U32(1))?;
-                    cursor_key.set_value("", &Data::String(U16CString::from_str(name)?))?;
-                    match Windows::get_user_scheme_key()?.value(U16CString::from_str(name)?)? {
-                        Data::String(data) => {
-                            cursors = Some(
-                                data.to_string()
-                                    .unwrap()
-                                    .split(",")
-                                    .map(|f| f.to_string())
-                                    .collect::<Vec<_>>(),
-                            );
-                        }
-                        _ => return Err(WindowsError::SchemeNotFound.into()),
-                    }
-                }
-                CursorScheme::SystemScheme(name) => {
-                    cursor_key.set_value("Scheme Source", &Data::U32(2))?;
-                    cursor_key.set_value("", &Data::String(U16CString::from_str(name)?))?;
-                    match Windows::get_system_scheme_key()?.value(U16CString::from_str(name)?)? {
-                        Data::String(data) => {
-                            cursors = Some(
-                                data.to_string()
-                                    .unwrap()
-                                    .split(",")
-                                    .map(|f| f.to_string())
-                                    .collect::<Vec<_>>(),
-                            );
-                        }
-                        _ => return Err(WindowsError::SchemeNotFound.into()),
-                    }
-                }
-            };
-            let cursors = cursors.unwrap_or_default();
-            for (i, item) in CURSOR_ORDER.iter().enumerate() {
-                cursor_key.set_value(
-                    *item,
-                    // if the cursor is not specified, use default of blank which will use system default
-                    &Data::ExpandString(U16CString::from_str(cursors.get(i).map_or("", |v| v))?),
-                )?;
-            }
-            unsafe {
-                winsafe::SystemParametersInfo(SPI::SETCURSORS, 0, &mut 0, SPIF::NoValue)?;
             }
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::connectors::registry_backend::MemoryRegistry;
+
+    fn windows_with(registry: &Arc<MemoryRegistry>) -> Windows {
+        Windows::with_registry(Box::new(registry.clone()))
+    }
+
+    fn cursor(registry: &MemoryRegistry, name: &str) -> Option<RegValue> {
+        registry
+            .get_value(RegHive::CurrentUser, CURSORS_KEY, name)
+            .unwrap()
+    }
+
+    #[test]
+    fn user_scheme_sets_every_cursor() {
+        let registry = Arc::new(MemoryRegistry::new());
+        registry
+            .set_value(
+                RegHive::CurrentUser,
+                USER_SCHEMES_KEY,
+                "Miku",
+                RegValue::String(r"C:\Miku\Arrow.cur,C:\Miku\Help.cur".to_string()),
+            )
+            .unwrap();
+        windows_with(&registry)
+            .set_cursor_scheme(&CursorScheme::UserScheme("Miku".to_string()))
+            .unwrap();
+        assert_eq!(cursor(&registry, "Scheme Source"), Some(RegValue::U32(1)));
+        assert_eq!(
+            cursor(&registry, ""),
+            Some(RegValue::String("Miku".to_string()))
+        );
+        assert_eq!(
+            cursor(&registry, "Arrow"),
+            Some(RegValue::ExpandString(r"C:\Miku\Arrow.cur".to_string()))
+        );
+        assert_eq!(
+            cursor(&registry, "Help"),
+            Some(RegValue::ExpandString(r"C:\Miku\Help.cur".to_string()))
+        );
+        // Cursors the scheme leaves out go back to the system default
+        assert_eq!(
+            cursor(&registry, "Hand"),
+            Some(RegValue::ExpandString(String::new()))
+        );
+    }
+
+    #[test]
+    fn system_scheme_is_read_from_local_machine() {
+        let registry = Arc::new(MemoryRegistry::new());
+        registry
+            .set_value(
+                RegHive::LocalMachine,
+                SYSTEM_SCHEMES_KEY,
+                "Windows Black",
+                RegValue::ExpandString(r"%SystemRoot%\cursors\arrow_r.cur".to_string()),
+            )
+            .unwrap();
+        windows_with(&registry)
+            .set_cursor_scheme(&CursorScheme::SystemScheme("Windows Black".to_string()))
+            .unwrap();
+        assert_eq!(cursor(&registry, "Scheme Source"), Some(RegValue::U32(2)));
+        assert_eq!(
+            cursor(&registry, "Arrow"),
+            Some(RegValue::ExpandString(
+                r"%SystemRoot%\cursors\arrow_r.cur".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn default_scheme_clears_cursors() {
+        let registry = Arc::new(MemoryRegistry::new());
+        windows_with(&registry)
+            .set_cursor_scheme(&CursorScheme::WindowsDefault)
+            .unwrap();
+        assert_eq!(cursor(&registry, "Scheme Source"), Some(RegValue::U32(0)));
+        assert_eq!(cursor(&registry, ""), Some(RegValue::String(String::new())));
+        for name in CURSOR_ORDER {
+            assert_eq!(
+                cursor(&registry, name),
+                Some(RegValue::ExpandString(String::new()))
+            );
+        }
+    }
+
+    #[test]
+    fn ui_font_is_restored_from_the_saved_entries() {
+        let registry = Arc::new(MemoryRegistry::new());
+        let font = |name: &str| {
+            registry
+                .get_value(RegHive::LocalMachine, FONTS_KEY, name)
+                .unwrap()
+        };
+        let file = |file: &str| Some(RegValue::String(file.to_string()));
+        for (name, value) in [
+            ("Segoe UI (TrueType)", "SEGOEUI.TTF"),
+            ("Segoe UI Bold (TrueType)", "segoeuib.ttf"),
+            ("Segoe UI Symbol (TrueType)", "seguisym.ttf"),
+            ("Segoe UI Variable (TrueType)", "SegUIVar.ttf"),
+        ] {
+            registry
+                .set_value(
+                    RegHive::LocalMachine,
+                    FONTS_KEY,
+                    name,
+                    RegValue::String(value.to_string()),
+                )
+                .unwrap();
+        }
+        let windows = windows_with(&registry);
+
+        windows.set_ui_font("Inter").unwrap();
+        // Replacing again must not save the emptied entries over the originals
+        windows.set_ui_font("Fira Sans").unwrap();
+        assert_eq!(font("Segoe UI (TrueType)"), file(""));
+        assert_eq!(font("Segoe UI Bold (TrueType)"), file(""));
+        assert_eq!(font("Segoe UI Symbol (TrueType)"), file("seguisym.ttf"));
+        assert_eq!(font("Segoe UI Variable (TrueType)"), file("SegUIVar.ttf"));
+        assert_eq!(
+            registry
+                .get_value(RegHive::LocalMachine, FONT_SUBSTITUTES_KEY, WINDOWS_UI_FONT)
+                .unwrap(),
+            file("Fira Sans")
+        );
+
+        windows.set_ui_font(WINDOWS_UI_FONT).unwrap();
+        assert_eq!(font("Segoe UI (TrueType)"), file("SEGOEUI.TTF"));
+        assert_eq!(font("Segoe UI Bold (TrueType)"), file("segoeuib.ttf"));
+        // Entries that could not be read get their stock file
+        assert_eq!(font("Segoe UI Light (TrueType)"), file("segoeuil.ttf"));
+        assert_eq!(font("Segoe UI Symbol (TrueType)"), file("seguisym.ttf"));
+        assert_eq!(
+            registry
+                .get_value(RegHive::LocalMachine, FONT_SUBSTITUTES_KEY, WINDOWS_UI_FONT)
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn missing_scheme_is_an_error() {
+        let registry = Arc::new(MemoryRegistry::new());
+        let error = windows_with(&registry)
+            .set_cursor_scheme(&CursorScheme::UserScheme("Missing".to_string()))
+            .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<WindowsError>(),
+            Some(WindowsError::SchemeNotFound { name }) if name == "Missing"
+        ));
+    }
+}
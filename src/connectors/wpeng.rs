@@ -4,22 +4,41 @@
   Controls: active wallpaper
 
   Config options:
-  - Wallpapers: Map<u8, Option<String>> - Screen number, name of wallpaper or None for no wallpaper.
+  - Wallpapers: Map<u8, Option<WallpaperId>> - Screen number, name or Workshop ID of wallpaper or None for no wallpaper.
+  NOTE: Names do not have to match exactly, see `wpeng_library`.
+  NOTE: Screen numbers are Wallpaper Engine's. `wpeng_monitor` finds them from the monitor layout.
+  - Playlists: Map<u8, String> - Screen number, name of a playlist to load instead of a wallpaper.
+  - Properties: Map<u8, Map<String, Value>> - Screen number, user properties for its wallpaper or playlist.
+  - Playback: Option<Playback> - Play, pause or stop every wallpaper. None = do not change
+  - Mute: Option<bool> - Whether wallpapers are muted. None = do not change
+  NOTE: Everything is sent through Wallpaper Engine's `-control` command line, see `wpeng_control`.
+  `wpeng_rs` is only used to find Wallpaper Engine and its wallpapers. It only wraps opening and removing
+  wallpapers, so every command is built by `wpeng_control` instead, and each one has to exit successfully.
 */
 
 const BITS: AutodetectType = AutodetectType::Default64BitMachine;
 
-use std::{collections::HashMap, error::Error, path::PathBuf};
+use std::{collections::HashMap, error::Error, path::PathBuf, process::Command};
+
+use serde_json::{Map, Value};
 
 use wpeng_rs::autodetect::{
     autodetect_wallpaper_engine_config, autodetect_wallpaper_engine_exe_from_config,
     autodetect_wallpaper_engine_wallpapers, AutodetectType,
 };
 
-use super::Connector;
+use super::{
+    wpeng_control::{Control, Playback},
+    wpeng_library::{self, Wallpaper, WallpaperId},
+    Connector,
+};
 
 pub struct Wpeng {
-    pub wallpapers: HashMap<u8, Option<String>>,
+    pub wallpapers: HashMap<u8, Option<WallpaperId>>,
+    pub playlists: HashMap<u8, String>,
+    pub properties: HashMap<u8, Map<String, Value>>,
+    pub playback: Option<Playback>,
+    pub mute: Option<bool>,
     pub exe: PathBuf,
 }
 
@@ -28,7 +47,7 @@ custom_error::custom_error! {pub WpengError
     ErrorDetectingExe = "Error detecting wallpaper engine exe",
     ErrorDetectingWallpapers = "Error detecting wallpaper engine wallpapers",
     ErrorLaunchingProcess = "Error launching wallpaper engine control process",
-    MissingWallpaper = "Selected a missing wallpaper"
+    ControlFailed{command: String, stderr: String} = "Wallpaper Engine failed to run `{command}`: {stderr}"
 }
 
 impl Wpeng {
@@ -39,8 +58,19 @@ impl Wpeng {
             .map(|item| (item.clone().name, item.path.to_str().unwrap().to_string()))
             .collect::<HashMap<_, _>>())
     }
+    fn installed_wallpapers() -> Result<Vec<Wallpaper>, WpengError> {
+        Ok(autodetect_wallpaper_engine_wallpapers(BITS)
+            .ok_or(WpengError::ErrorDetectingWallpapers)?
+            .iter()
+            .map(|item| Wallpaper {
+                name: item.name.clone(),
+                path: item.path.to_str().unwrap().to_string(),
+                workshop_id: wpeng_library::read_workshop_id(&item.path),
+            })
+            .collect())
+    }
 
-    pub fn set_wallpaper(&mut self, screen: u8, wallpaper: Option<String>) {
+    pub fn set_wallpaper(&mut self, screen: u8, wallpaper: Option<WallpaperId>) {
         match self.wallpapers.get_mut(&screen) {
             Some(item) => {
                 *item = wallpaper;
@@ -50,12 +80,59 @@ impl Wpeng {
             }
         }
     }
-    pub fn get_wallpaper(self, screen: u8) -> Option<Option<String>> {
+    pub fn get_wallpaper(self, screen: u8) -> Option<Option<WallpaperId>> {
         self.wallpapers.get(&screen).cloned()
     }
     pub fn get_wallpaper_options(self) -> Result<HashMap<String, String>, WpengError> {
         Wpeng::wallpapers_hashmap()
     }
+    /// The control commands `apply` runs, in order, given the installed wallpapers.
+    pub fn controls(
+        &self,
+        installed_wallpapers: &[Wallpaper],
+    ) -> Result<Vec<Control>, Box<dyn Error + 'static>> {
+        let mut controls = vec![];
+        let mut screens = self
+            .wallpapers
+            .keys()
+            .chain(self.playlists.keys())
+            .copied()
+            .collect::<Vec<_>>();
+        screens.sort();
+        screens.dedup();
+        for screen in screens {
+            let monitor = Some(screen);
+            let properties = self.properties.get(&screen).cloned().unwrap_or_default();
+            if let Some(playlist) = self.playlists.get(&screen) {
+                controls.push(Control::OpenPlaylist {
+                    name: playlist.clone(),
+                    monitor,
+                });
+                if !properties.is_empty() {
+                    controls.push(Control::ApplyProperties {
+                        monitor,
+                        properties,
+                    });
+                }
+                continue;
+            }
+            match self.wallpapers.get(&screen).cloned().flatten() {
+                Some(id) => controls.push(Control::OpenWallpaper {
+                    file: wpeng_library::find(installed_wallpapers, &id)?.path.clone(),
+                    monitor,
+                    properties,
+                }),
+                None => controls.push(Control::RemoveWallpaper { monitor }),
+            }
+        }
+        if let Some(mute) = self.mute {
+            controls.push(Control::Mute(mute));
+        }
+        if let Some(playback) = self.playback {
+            controls.push(Control::Playback(playback));
+        }
+        Ok(controls)
+    }
 }
 
 #[async_trait::async_trait]
@@ -67,36 +144,32 @@ impl Connector for Wpeng {
             .ok_or(WpengError::ErrorDetectingExe)?;
         Ok(Self {
             wallpapers: HashMap::new(),
+            playlists: HashMap::new(),
+            properties: HashMap::new(),
+            playback: None,
+            mute: None,
             exe,
         })
     }
     fn verify(&self) -> Result<(), Box<dyn Error + 'static>> {
         let _ = autodetect_wallpaper_engine_config(BITS).ok_or(WpengError::ErrorDetectingConfig)?;
+        // Catches misspelled names before anything is changed
+        let _ = self.controls(&Wpeng::installed_wallpapers()?)?;
         Ok(())
     }
     async fn apply(&self) -> Result<(), Box<dyn Error + 'static>> {
-        let available_wallpapers = Wpeng::wallpapers_hashmap()?;
-        for (key, value) in self.wallpapers.clone().into_iter() {
-            if let Some(name) = value {
-                match available_wallpapers.get(&name) {
-                    Some(path) => {
-                        wpeng_rs::open_wallpaper(
-                            self.exe.clone(),
-                            path.to_string(),
-                            None,
-                            Some(key),
-                            None,
-                            None,
-                        )
-                        .map_err(|_| WpengError::ErrorLaunchingProcess)?;
-                    }
-                    None => {
-                        return Err(WpengError::MissingWallpaper.into());
-                    }
+        for control in self.controls(&Wpeng::installed_wallpapers()?)? {
+            let args = control.args();
+            let output = Command::new(&self.exe)
+                .args(&args)
+                .output()
+                .map_err(|_| WpengError::ErrorLaunchingProcess)?;
+            if !output.status.success() {
+                return Err(WpengError::ControlFailed {
+                    command: args.join(" "),
+                    stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
                 }
-            } else {
-                wpeng_rs::remove_wallpaper(self.exe.clone(), None, Some(key))
-                    .map_err(|_| WpengError::ErrorLaunchingProcess)?;
+                .into());
             }
         }
         Ok(())
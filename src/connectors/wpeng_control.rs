@@ -0,0 +1,311 @@
+/*
+  Wallpaper Engine control commands
+  Used by: wpeng
+
+  Builds the arguments for Wallpaper Engine's `-control` command line, for every command, including
+  the two `wpeng_rs` has wrappers for. Nothing here runs Wallpaper Engine, so it works the same on
+  every platform.
+*/
+
+use std::{collections::BTreeMap, str::FromStr};
+
+use serde_json::{Map, Value};
+
+use crate::{
+    palette::{Color, Palette},
+    toml::PropertyValue,
+};
+
+custom_error::custom_error! {pub ControlError
+    InvalidPlayback{value: String} = "invalid playback (expected play, pause or stop): {value}"
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Playback {
+    Play,
+    Pause,
+    Stop,
+}
+
+impl FromStr for Playback {
+    type Err = ControlError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "play" => Ok(Playback::Play),
+            "pause" => Ok(Playback::Pause),
+            "stop" => Ok(Playback::Stop),
+            _ => Err(ControlError::InvalidPlayback {
+                value: s.to_string(),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Control {
+    OpenWallpaper {
+        file: String,
+        monitor: Option<u8>,
+        properties: Map<String, Value>,
+    },
+    OpenPlaylist {
+        name: String,
+        monitor: Option<u8>,
+    },
+    ApplyProperties {
+        monitor: Option<u8>,
+        properties: Map<String, Value>,
+    },
+    RemoveWallpaper {
+        monitor: Option<u8>,
+    },
+    Playback(Playback),
+    Mute(bool),
+}
+
+impl Control {
+    // Wallpaper Engine takes properties as JSON wrapped in `RAW~(` and `)~END`
+    fn properties_arg(properties: &Map<String, Value>) -> String {
+        format!("RAW~({})~END", Value::Object(properties.clone()))
+    }
+    fn monitor_args(args: &mut Vec<String>, monitor: &Option<u8>) {
+        if let Some(monitor) = monitor {
+            args.push("-monitor".to_string());
+            args.push(monitor.to_string());
+        }
+    }
+    /// Everything after the executable.
+    pub fn args(&self) -> Vec<String> {
+        let mut args = vec!["-control".to_string()];
+        match self {
+            Control::OpenWallpaper {
+                file,
+                monitor,
+                properties,
+            } => {
+                args.push("openWallpaper".to_string());
+                args.push("-file".to_string());
+                args.push(file.clone());
+                Control::monitor_args(&mut args, monitor);
+                if !properties.is_empty() {
+                    args.push("-properties".to_string());
+                    args.push(Control::properties_arg(properties));
+                }
+            }
+            Control::OpenPlaylist { name, monitor } => {
+                args.push("openPlaylist".to_string());
+                args.push("-playlist".to_string());
+                args.push(name.clone());
+                Control::monitor_args(&mut args, monitor);
+            }
+            Control::ApplyProperties {
+                monitor,
+                properties,
+            } => {
+                args.push("applyProperties".to_string());
+                Control::monitor_args(&mut args, monitor);
+                args.push("-properties".to_string());
+                args.push(Control::properties_arg(properties));
+            }
+            Control::RemoveWallpaper { monitor } => {
+                args.push("removeWallpaper".to_string());
+                Control::monitor_args(&mut args, monitor);
+            }
+            Control::Playback(playback) => args.push(
+                match playback {
+                    Playback::Play => "play",
+                    Playback::Pause => "pause",
+                    Playback::Stop => "stop",
+                }
+                .to_string(),
+            ),
+            Control::Mute(mute) => {
+                args.push(if *mute { "mute" } else { "unmute" }.to_string());
+            }
+        }
+        args
+    }
+}
+
+/// Wallpaper Engine colors are three channels from 0 to 1, separated by spaces.
+pub fn color_value(color: &Color) -> String {
+    let channel = |c: u8| {
+        let value = format!("{:.5}", c as f32 / 255.0);
+        value
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string()
+    };
+    format!(
+        "{} {} {}",
+        channel(color.r),
+        channel(color.g),
+        channel(color.b)
+    )
+}
+
+/// Turns config properties into the JSON Wallpaper Engine expects, taking named colors from
+/// `palette`. Text that is not a color is passed through, for combo and text properties.
+pub fn properties(
+    values: &BTreeMap<String, PropertyValue>,
+    palette: Option<&Palette>,
+) -> Map<String, Value> {
+    values
+        .iter()
+        .map(|(key, value)| {
+            let value = match value {
+                PropertyValue::Bool(value) => Value::Bool(*value),
+                PropertyValue::Number(value) => serde_json::Number::from_f64(*value)
+                    .map(Value::Number)
+                    .unwrap_or(Value::Null),
+                PropertyValue::Text(text) => {
+                    let color = palette.and_then(|palette| palette.role(text)).or_else(|| {
+                        text.starts_with('#')
+                            .then(|| Color::from_str(text).ok())
+                            .flatten()
+                    });
+                    match color {
+                        Some(color) => Value::String(color_value(&color)),
+                        None => Value::String(text.clone()),
+                    }
+                }
+            };
+            (key.clone(), value)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn object(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn open_wallpaper_with_properties() {
+        assert_eq!(
+            Control::OpenWallpaper {
+                file: r"C:\wallpapers\123\project.json".to_string(),
+                monitor: Some(1),
+                properties: object(json!({"schemecolor": "1 0 0.5"})),
+            }
+            .args(),
+            [
+                "-control",
+                "openWallpaper",
+                "-file",
+                r"C:\wallpapers\123\project.json",
+                "-monitor",
+                "1",
+                "-properties",
+                r#"RAW~({"schemecolor":"1 0 0.5"})~END"#,
+            ]
+        );
+        // Without a monitor or properties, neither flag is passed
+        assert_eq!(
+            Control::OpenWallpaper {
+                file: "project.json".to_string(),
+                monitor: None,
+                properties: Map::new(),
+            }
+            .args(),
+            ["-control", "openWallpaper", "-file", "project.json"]
+        );
+    }
+
+    #[test]
+    fn playlists_and_properties() {
+        assert_eq!(
+            Control::OpenPlaylist {
+                name: "Evening".to_string(),
+                monitor: Some(0),
+            }
+            .args(),
+            [
+                "-control",
+                "openPlaylist",
+                "-playlist",
+                "Evening",
+                "-monitor",
+                "0"
+            ]
+        );
+        assert_eq!(
+            Control::ApplyProperties {
+                monitor: None,
+                properties: object(json!({"rain": true})),
+            }
+            .args(),
+            [
+                "-control",
+                "applyProperties",
+                "-properties",
+                r#"RAW~({"rain":true})~END"#
+            ]
+        );
+        assert_eq!(
+            Control::RemoveWallpaper { monitor: Some(2) }.args(),
+            ["-control", "removeWallpaper", "-monitor", "2"]
+        );
+    }
+
+    #[test]
+    fn playback_and_mute() {
+        for (playback, name) in [("Play", "play"), ("pause", "pause"), ("STOP", "stop")] {
+            assert_eq!(
+                Control::Playback(Playback::from_str(playback).unwrap()).args(),
+                ["-control", name]
+            );
+        }
+        assert!(Playback::from_str("rewind").is_err());
+        assert_eq!(Control::Mute(true).args(), ["-control", "mute"]);
+        assert_eq!(Control::Mute(false).args(), ["-control", "unmute"]);
+    }
+
+    #[test]
+    fn property_values() {
+        let palette: Palette = toml::from_str(
+            r##"
+            background = "#000000"
+            foreground = "#ffffff"
+            accent = "#ff0080"
+            colors = ["#000000", "#ff0000"]
+            "##,
+        )
+        .unwrap();
+        let values = BTreeMap::from([
+            (
+                "accent".to_string(),
+                PropertyValue::Text("accent".to_string()),
+            ),
+            ("red".to_string(), PropertyValue::Text("color1".to_string())),
+            (
+                "hex".to_string(),
+                PropertyValue::Text("#00ff00".to_string()),
+            ),
+            ("mode".to_string(), PropertyValue::Text("snow".to_string())),
+            ("rain".to_string(), PropertyValue::Bool(false)),
+            ("speed".to_string(), PropertyValue::Number(0.5)),
+        ]);
+        assert_eq!(
+            Value::Object(properties(&values, Some(&palette))),
+            json!({
+                "accent": "1 0 0.50196",
+                "red": "1 0 0",
+                "hex": "0 1 0",
+                "mode": "snow",
+                "rain": false,
+                "speed": 0.5,
+            })
+        );
+        // Without a palette, role names stay text
+        assert_eq!(
+            properties(&values, None)["accent"],
+            Value::String("accent".to_string())
+        );
+    }
+}
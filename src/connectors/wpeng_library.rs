@@ -0,0 +1,281 @@
+/*
+  Wallpaper Engine library lookup
+  Used by: wpeng
+
+  Finds installed wallpapers by Workshop ID or by name. Workshop IDs come from each wallpaper's
+  `project.json`, so a wallpaper can still be found after its author renames it. Names are
+  matched loosely, since they are easy to get slightly wrong when typing them into a preset.
+*/
+
+use std::{fs, path::Path};
+
+use serde_json::Value;
+
+custom_error::custom_error! {pub LibraryError
+    WorkshopIdNotFound{id: String} = "no installed wallpaper has Workshop ID {id}",
+    NameNotFound{name: String} = "wallpaper not found: {name}",
+    NameNotFoundSuggestion{name: String, suggestion: String} = "wallpaper not found: {name} (did you mean \"{suggestion}\"?)",
+    Ambiguous{name: String, matches: String} = "wallpaper name {name} matches more than one wallpaper: {matches}"
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Wallpaper {
+    pub name: String,
+    // What Wallpaper Engine is given to open it
+    pub path: String,
+    pub workshop_id: Option<String>,
+}
+
+/// How a preset picks a wallpaper.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WallpaperId {
+    Name(String),
+    Workshop(String),
+}
+
+/// The Workshop ID in a `project.json`. Wallpaper Engine writes it as a string, but older
+/// projects have it as a number.
+pub fn project_workshop_id(contents: &str) -> Option<String> {
+    let project: Value = serde_json::from_str(contents).ok()?;
+    match project.get("workshopid")? {
+        Value::String(id) if !id.is_empty() => Some(id.clone()),
+        Value::Number(id) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+/// Reads the Workshop ID of the wallpaper at `path`, which is its folder or its `project.json`.
+/// Workshop folders are named after the ID, which is used when `project.json` does not have one.
+pub fn read_workshop_id(path: &Path) -> Option<String> {
+    let dir = if path.is_dir() { path } else { path.parent()? };
+    fs::read_to_string(dir.join("project.json"))
+        .ok()
+        .and_then(|contents| project_workshop_id(&contents))
+        .or_else(|| {
+            let folder = dir.file_name()?.to_str()?;
+            folder
+                .chars()
+                .all(|c| c.is_ascii_digit())
+                .then(|| folder.to_string())
+        })
+}
+
+// Lowercase, with only letters and digits, so spacing and punctuation do not matter
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+// The only wallpaper in `matches`, or an error listing them when there is more than one
+fn only<'a>(
+    name: &str,
+    matches: Vec<&'a Wallpaper>,
+) -> Result<Option<&'a Wallpaper>, LibraryError> {
+    match matches.as_slice() {
+        [] => Ok(None),
+        [wallpaper] => Ok(Some(*wallpaper)),
+        matches => Err(LibraryError::Ambiguous {
+            name: name.to_string(),
+            matches: matches
+                .iter()
+                .map(|wallpaper| format!("\"{}\"", wallpaper.name))
+                .collect::<Vec<_>>()
+                .join(", "),
+        }),
+    }
+}
+
+/// Finds a wallpaper by name. An exact match wins, then a match ignoring case, spacing and
+/// punctuation, then a wallpaper whose name contains `name`. More than one match at the first
+/// step that finds any is an error.
+pub fn find_by_name<'a>(
+    wallpapers: &'a [Wallpaper],
+    name: &str,
+) -> Result<&'a Wallpaper, LibraryError> {
+    if let Some(wallpaper) = wallpapers.iter().find(|wallpaper| wallpaper.name == name) {
+        return Ok(wallpaper);
+    }
+    let wanted = normalize(name);
+    let same = wallpapers
+        .iter()
+        .filter(|wallpaper| normalize(&wallpaper.name) == wanted)
+        .collect();
+    if let Some(wallpaper) = only(name, same)? {
+        return Ok(wallpaper);
+    }
+    let containing = wallpapers
+        .iter()
+        .filter(|wallpaper| !wanted.is_empty() && normalize(&wallpaper.name).contains(&wanted))
+        .collect();
+    if let Some(wallpaper) = only(name, containing)? {
+        return Ok(wallpaper);
+    }
+    // Only suggest names that are reasonably close
+    let suggestion = wallpapers
+        .iter()
+        .map(|wallpaper| {
+            (
+                edit_distance(&normalize(&wallpaper.name), &wanted),
+                wallpaper,
+            )
+        })
+        .filter(|(distance, _)| *distance <= wanted.chars().count() / 3 + 1)
+        .min_by_key(|(distance, _)| *distance);
+    match suggestion {
+        Some((_, wallpaper)) => Err(LibraryError::NameNotFoundSuggestion {
+            name: name.to_string(),
+            suggestion: wallpaper.name.clone(),
+        }),
+        None => Err(LibraryError::NameNotFound {
+            name: name.to_string(),
+        }),
+    }
+}
+
+/// Finds a wallpaper by Workshop ID or name.
+pub fn find<'a>(
+    wallpapers: &'a [Wallpaper],
+    id: &WallpaperId,
+) -> Result<&'a Wallpaper, LibraryError> {
+    match id {
+        WallpaperId::Name(name) => find_by_name(wallpapers, name),
+        WallpaperId::Workshop(workshop_id) => wallpapers
+            .iter()
+            .find(|wallpaper| wallpaper.workshop_id.as_ref() == Some(workshop_id))
+            .ok_or_else(|| LibraryError::WorkshopIdNotFound {
+                id: workshop_id.clone(),
+            }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wallpapers(names: &[(&str, Option<&str>)]) -> Vec<Wallpaper> {
+        names
+            .iter()
+            .map(|(name, workshop_id)| Wallpaper {
+                name: name.to_string(),
+                path: format!(r"C:\wallpapers\{}\project.json", name),
+                workshop_id: workshop_id.map(str::to_string),
+            })
+            .collect()
+    }
+
+    fn library() -> Vec<Wallpaper> {
+        wallpapers(&[
+            ("Rainy Night City", Some("1234")),
+            ("Miku Sunset", Some("5678")),
+            ("Ocean", None),
+            ("Ocean Waves", None),
+        ])
+    }
+
+    fn name(result: Result<&Wallpaper, LibraryError>) -> String {
+        result.unwrap().name.clone()
+    }
+
+    #[test]
+    fn exact_names_win() {
+        // "Ocean" is also part of "Ocean Waves"
+        assert_eq!(name(find_by_name(&library(), "Ocean")), "Ocean");
+        let library = wallpapers(&[("miku sunset", None), ("Miku Sunset", None)]);
+        assert_eq!(name(find_by_name(&library, "Miku Sunset")), "Miku Sunset");
+    }
+
+    #[test]
+    fn names_ignore_case_spacing_and_punctuation() {
+        assert_eq!(
+            name(find_by_name(&library(), "rainy night city")),
+            "Rainy Night City"
+        );
+        assert_eq!(
+            name(find_by_name(&library(), "miku-sunset!")),
+            "Miku Sunset"
+        );
+        assert_eq!(name(find_by_name(&library(), "night")), "Rainy Night City");
+    }
+
+    #[test]
+    fn ties_are_ambiguous() {
+        let same = wallpapers(&[("Miku-Sunset", None), ("miku sunset", None)]);
+        assert!(matches!(
+            find_by_name(&same, "Miku Sunset"),
+            Err(LibraryError::Ambiguous { matches, .. }) if matches == "\"Miku-Sunset\", \"miku sunset\""
+        ));
+        assert!(matches!(
+            find_by_name(&library(), "ocea"),
+            Err(LibraryError::Ambiguous { matches, .. }) if matches == "\"Ocean\", \"Ocean Waves\""
+        ));
+    }
+
+    #[test]
+    fn near_misses_get_a_suggestion() {
+        assert!(matches!(
+            find_by_name(&library(), "Miku Sunzet"),
+            Err(LibraryError::NameNotFoundSuggestion { suggestion, .. }) if suggestion == "Miku Sunset"
+        ));
+        // Equally close names suggest the first
+        let library = wallpapers(&[("Cats", None), ("Bats", None)]);
+        assert!(matches!(
+            find_by_name(&library, "Rats"),
+            Err(LibraryError::NameNotFoundSuggestion { suggestion, .. }) if suggestion == "Cats"
+        ));
+        assert!(matches!(
+            find_by_name(&library, "Something else"),
+            Err(LibraryError::NameNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn finds_by_workshop_id() {
+        let library = library();
+        assert_eq!(
+            name(find(&library, &WallpaperId::Workshop("5678".to_string()))),
+            "Miku Sunset"
+        );
+        assert!(matches!(
+            find(&library, &WallpaperId::Workshop("9".to_string())),
+            Err(LibraryError::WorkshopIdNotFound { id }) if id == "9"
+        ));
+    }
+
+    #[test]
+    fn edit_distances() {
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("miku", "miku"), 0);
+    }
+
+    #[test]
+    fn workshop_ids_from_project_json() {
+        assert_eq!(
+            project_workshop_id(r#"{"title": "Ocean", "workshopid": "1234"}"#),
+            Some("1234".to_string())
+        );
+        assert_eq!(
+            project_workshop_id(r#"{"workshopid": 1234}"#),
+            Some("1234".to_string())
+        );
+        assert_eq!(project_workshop_id(r#"{"title": "Ocean"}"#), None);
+        assert_eq!(project_workshop_id(r#"{"workshopid": ""}"#), None);
+        assert_eq!(project_workshop_id("not json"), None);
+    }
+}
@@ -0,0 +1,363 @@
+/*
+  Wallpaper Engine monitor targeting
+  Used by: wpeng
+
+  Wallpaper Engine numbers monitors in the order Windows lists them, which changes when monitors
+  are plugged back in differently. This picks a monitor by where it is, its resolution or its name
+  instead, and turns that into the number Wallpaper Engine wants. Only reading the current layout
+  needs Windows, resolving a target works the same on every platform.
+*/
+
+use std::str::FromStr;
+
+custom_error::custom_error! {pub MonitorError
+    NotFound{target: String} = "no monitor matches {target}",
+    Ambiguous{target: String, matches: String} = "more than one monitor matches {target}: {matches}",
+    EmptyTarget = "monitor target is empty"
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Monitor {
+    // Wallpaper Engine's number for this monitor
+    pub index: u8,
+    // Like `\\.\DISPLAY1`
+    pub device_name: String,
+    // Model name from the monitor's EDID, like `DELL U2415`
+    pub model: Option<String>,
+    // EDID manufacturer and product code, like `DEL4123`
+    pub hardware_id: Option<String>,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub primary: bool,
+}
+
+/// How a preset picks a monitor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MonitorTarget {
+    Primary,
+    Left,
+    Right,
+    Resolution { width: u32, height: u32 },
+    // Device name, EDID model name or EDID hardware ID, matched whole and ignoring case
+    Name(String),
+}
+
+impl FromStr for MonitorTarget {
+    type Err = MonitorError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let target = s.trim();
+        if target.is_empty() {
+            return Err(MonitorError::EmptyTarget);
+        }
+        match target.to_lowercase().as_str() {
+            "primary" => return Ok(MonitorTarget::Primary),
+            "left" => return Ok(MonitorTarget::Left),
+            "right" => return Ok(MonitorTarget::Right),
+            _ => (),
+        }
+        let resolution = target
+            .split_once(['x', 'X'])
+            .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)));
+        Ok(match resolution {
+            Some((width, height)) => MonitorTarget::Resolution { width, height },
+            None => MonitorTarget::Name(target.to_string()),
+        })
+    }
+}
+
+/// The EDID hardware ID and instance in a monitor's device interface name, like `DEL4123` and
+/// `5&1a2b3c&0&UID4352` in `\\?\DISPLAY#DEL4123#5&1a2b3c&0&UID4352#{e6f07b5f-ee97-4a90-b076-33f57bf4eaa7}`.
+pub fn interface_ids(interface: &str) -> Option<(String, String)> {
+    let mut parts = interface.strip_prefix(r"\\?\")?.split('#');
+    if !parts.next()?.eq_ignore_ascii_case("DISPLAY") {
+        return None;
+    }
+    let hardware_id = parts.next().filter(|id| !id.is_empty())?;
+    let instance = parts.next().filter(|id| !id.is_empty())?;
+    Some((hardware_id.to_string(), instance.to_string()))
+}
+
+/// The model name in an EDID block, from its display product name descriptor.
+pub fn edid_model(edid: &[u8]) -> Option<String> {
+    // Four 18 byte descriptors start at byte 54, the name is the one tagged 0xFC. It is up to 13
+    // characters, ended by a newline when shorter
+    edid.get(54..126)?
+        .chunks(18)
+        .find(|descriptor| descriptor[..3] == [0, 0, 0] && descriptor[3] == 0xFC)
+        .and_then(|descriptor| {
+            let name = String::from_utf8_lossy(&descriptor[5..]);
+            let name = name.split('\n').next()?.trim();
+            (!name.is_empty()).then(|| name.to_string())
+        })
+}
+
+fn describe(target: &MonitorTarget) -> String {
+    match target {
+        MonitorTarget::Primary => "primary".to_string(),
+        MonitorTarget::Left => "left".to_string(),
+        MonitorTarget::Right => "right".to_string(),
+        MonitorTarget::Resolution { width, height } => format!("{}x{}", width, height),
+        MonitorTarget::Name(name) => name.clone(),
+    }
+}
+
+// Names are compared whole, ignoring case, so `DELL U2415` never picks a `DELL U2415H`
+fn names_match(monitor: &Monitor, name: &str) -> bool {
+    let device_name = monitor.device_name.trim_start_matches(['\\', '.']);
+    device_name.eq_ignore_ascii_case(name.trim_start_matches(['\\', '.']))
+        || [&monitor.hardware_id, &monitor.model]
+            .into_iter()
+            .flatten()
+            .any(|id| id.eq_ignore_ascii_case(name))
+}
+
+/// Picks the monitor `target` means in `layout` and returns Wallpaper Engine's number for it.
+/// `left` and `right` are the monitors furthest to that side, higher up winning a tie.
+pub fn resolve(layout: &[Monitor], target: &MonitorTarget) -> Result<u8, MonitorError> {
+    let matches: Vec<&Monitor> = match target {
+        MonitorTarget::Primary => layout.iter().filter(|monitor| monitor.primary).collect(),
+        MonitorTarget::Left => layout
+            .iter()
+            .min_by_key(|monitor| (monitor.x, monitor.y))
+            .into_iter()
+            .collect(),
+        MonitorTarget::Right => layout
+            .iter()
+            .max_by_key(|monitor| (monitor.x + monitor.width as i32, -monitor.y))
+            .into_iter()
+            .collect(),
+        MonitorTarget::Resolution { width, height } => layout
+            .iter()
+            .filter(|monitor| monitor.width == *width && monitor.height == *height)
+            .collect(),
+        MonitorTarget::Name(name) => layout
+            .iter()
+            .filter(|monitor| names_match(monitor, name))
+            .collect(),
+    };
+    match matches.as_slice() {
+        [monitor] => Ok(monitor.index),
+        [] => Err(MonitorError::NotFound {
+            target: describe(target),
+        }),
+        monitors => Err(MonitorError::Ambiguous {
+            target: describe(target),
+            matches: monitors
+                .iter()
+                .map(|monitor| monitor.device_name.clone())
+                .collect::<Vec<_>>()
+                .join(", "),
+        }),
+    }
+}
+
+/// The monitors connected right now, numbered in the order Windows lists them, which is the
+/// order Wallpaper Engine uses.
+#[cfg(windows)]
+pub fn current_layout() -> Result<Vec<Monitor>, Box<dyn std::error::Error + 'static>> {
+    use winsafe::{co, prelude::*, EnumDisplayDevices, HDC};
+
+    use super::registry_backend::{self, RegHive, RegValue};
+
+    let registry = registry_backend::system_registry();
+    let mut handles = vec![];
+    HDC::NULL.EnumDisplayMonitors(None, |monitor, _, _| {
+        handles.push(monitor);
+        true
+    })?;
+    let mut layout = vec![];
+    for (index, handle) in handles.iter().enumerate() {
+        let info = handle.GetMonitorInfo()?;
+        let device_name = info.szDevice();
+        // The first device under the adapter is the monitor itself. Its interface name leads
+        // to the EDID, since the device string is only a generic "PnP Monitor"
+        let ids = EnumDisplayDevices(Some(&device_name), Some(co::EDD::GET_DEVICE_INTERFACE_NAME))
+            .next()
+            .transpose()?
+            .and_then(|device| interface_ids(&device.DeviceID()));
+        let edid = match (&ids, &registry) {
+            (Some((hardware_id, instance)), Some(registry)) => {
+                let key = format!(
+                    r"SYSTEM\CurrentControlSet\Enum\DISPLAY\{}\{}\Device Parameters",
+                    hardware_id, instance
+                );
+                match registry.get_value(RegHive::LocalMachine, &key, "EDID") {
+                    Ok(Some(RegValue::Binary(edid))) => Some(edid),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        let rect = info.rcMonitor;
+        layout.push(Monitor {
+            index: index as u8,
+            device_name,
+            model: edid.as_deref().and_then(edid_model),
+            hardware_id: ids.map(|(hardware_id, _)| hardware_id),
+            x: rect.left,
+            y: rect.top,
+            width: (rect.right - rect.left) as u32,
+            height: (rect.bottom - rect.top) as u32,
+            primary: u32::from(info.dwFlags) & u32::from(co::MONITORINFOF::PRIMARY) != 0,
+        });
+    }
+    Ok(layout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(index: u8, x: i32, y: i32, width: u32, height: u32) -> Monitor {
+        Monitor {
+            index,
+            device_name: format!(r"\\.\DISPLAY{}", index + 1),
+            model: None,
+            hardware_id: None,
+            x,
+            y,
+            width,
+            height,
+            primary: false,
+        }
+    }
+
+    // A laptop panel in the middle, a portrait monitor to its left, two external ones to its right
+    fn layout() -> Vec<Monitor> {
+        vec![
+            Monitor {
+                model: Some("DELL U2415".to_string()),
+                hardware_id: Some("DEL4123".to_string()),
+                ..monitor(0, 1920, 0, 1920, 1200)
+            },
+            Monitor {
+                primary: true,
+                ..monitor(1, 0, 0, 1920, 1080)
+            },
+            Monitor {
+                model: Some("DELL U2415H".to_string()),
+                hardware_id: Some("DEL4124".to_string()),
+                ..monitor(2, 3840, 0, 2560, 1440)
+            },
+            monitor(3, -1080, -400, 1080, 1920),
+        ]
+    }
+
+    fn target(target: &str) -> MonitorTarget {
+        MonitorTarget::from_str(target).unwrap()
+    }
+
+    #[test]
+    fn parses_targets() {
+        assert_eq!(target("Primary"), MonitorTarget::Primary);
+        assert_eq!(target(" left "), MonitorTarget::Left);
+        assert_eq!(target("RIGHT"), MonitorTarget::Right);
+        assert_eq!(
+            target("2560x1440"),
+            MonitorTarget::Resolution {
+                width: 2560,
+                height: 1440
+            }
+        );
+        assert_eq!(
+            target("1920X1080"),
+            MonitorTarget::Resolution {
+                width: 1920,
+                height: 1080
+            }
+        );
+        assert_eq!(
+            target("DELL U2415"),
+            MonitorTarget::Name("DELL U2415".to_string())
+        );
+        // Only numbers on both sides make a resolution
+        assert_eq!(
+            target("4x4 Max"),
+            MonitorTarget::Name("4x4 Max".to_string())
+        );
+        assert!(matches!(
+            MonitorTarget::from_str("  "),
+            Err(MonitorError::EmptyTarget)
+        ));
+    }
+
+    #[test]
+    fn resolves_by_position() {
+        let layout = layout();
+        assert_eq!(resolve(&layout, &target("primary")).unwrap(), 1);
+        assert_eq!(resolve(&layout, &target("left")).unwrap(), 3);
+        assert_eq!(resolve(&layout, &target("right")).unwrap(), 2);
+        // Side by side at the same edge, the higher one wins
+        let stacked = vec![
+            monitor(0, 0, 1080, 1920, 1080),
+            monitor(1, 0, 0, 1920, 1080),
+        ];
+        assert_eq!(resolve(&stacked, &target("left")).unwrap(), 1);
+        assert_eq!(resolve(&stacked, &target("right")).unwrap(), 1);
+    }
+
+    #[test]
+    fn resolves_by_resolution() {
+        let layout = layout();
+        assert_eq!(resolve(&layout, &target("2560x1440")).unwrap(), 2);
+        assert!(matches!(
+            resolve(&layout, &target("3840x2160")),
+            Err(MonitorError::NotFound { .. })
+        ));
+        let twins = vec![
+            monitor(0, 0, 0, 1920, 1080),
+            monitor(1, 1920, 0, 1920, 1080),
+        ];
+        match resolve(&twins, &target("1920x1080")) {
+            Err(MonitorError::Ambiguous { matches, .. }) => {
+                assert_eq!(matches, r"\\.\DISPLAY1, \\.\DISPLAY2")
+            }
+            result => panic!("expected an ambiguous match, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn resolves_by_name() {
+        let layout = layout();
+        assert_eq!(resolve(&layout, &target("DISPLAY2")).unwrap(), 1);
+        assert_eq!(resolve(&layout, &target(r"\\.\display4")).unwrap(), 3);
+        assert_eq!(resolve(&layout, &target("del4124")).unwrap(), 2);
+        // A model name is not a prefix of a longer one
+        assert_eq!(resolve(&layout, &target("dell u2415")).unwrap(), 0);
+        assert_eq!(resolve(&layout, &target("DELL U2415H")).unwrap(), 2);
+        assert!(matches!(
+            resolve(&layout, &target("DELL")),
+            Err(MonitorError::NotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn reads_interface_ids() {
+        assert_eq!(
+            interface_ids(
+                r"\\?\DISPLAY#DEL4123#5&1a2b3c&0&UID4352#{e6f07b5f-ee97-4a90-b076-33f57bf4eaa7}"
+            ),
+            Some(("DEL4123".to_string(), "5&1a2b3c&0&UID4352".to_string()))
+        );
+        assert_eq!(
+            interface_ids(r"MONITOR\DEL4123\{4d36e96e-e325-11ce-bfc1-08002be10318}\0001"),
+            None
+        );
+    }
+
+    #[test]
+    fn reads_edid_model() {
+        let mut edid = vec![0; 128];
+        // A serial number descriptor first, then the name
+        edid[54..59].copy_from_slice(&[0, 0, 0, 0xFF, 0]);
+        edid[59..72].copy_from_slice(b"ABC123456789\n");
+        edid[72..77].copy_from_slice(&[0, 0, 0, 0xFC, 0]);
+        edid[77..90].copy_from_slice(b"DELL U2415\n  ");
+        assert_eq!(edid_model(&edid), Some("DELL U2415".to_string()));
+        edid[75] = 0xFD;
+        assert_eq!(edid_model(&edid), None);
+        assert_eq!(edid_model(&edid[..100]), None);
+    }
+}
@@ -0,0 +1,180 @@
+/*
+  XCursor connector
+  Name: xcursor
+  Controls: Linux cursor theme and size
+
+  Config options:
+  - Theme: Option<String> - Name of an installed XCursor theme. None = do not change
+  - Size: Option<u32> - Cursor size in pixels. None = do not change
+  - Xresources: bool - Whether to write `Xcursor.*` to `~/.Xresources`. Off when `[[xresources]]` writes them
+  NOTE: There is no single place Linux reads the cursor from, so this sets all of them: the default
+  icon theme, `XCURSOR_THEME`/`XCURSOR_SIZE` through environment.d, X resources, GTK, GNOME and Hyprland.
+  Some apps only pick up the new cursor after logging out and back in.
+*/
+
+use std::{
+    env,
+    error::Error,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    edit::{self, block, ini},
+    process, xdg,
+};
+
+use super::Connector;
+
+custom_error::custom_error! {pub XcursorError
+    MissingHome = "could not find the home directory",
+    ThemeNotFound{name: String} = "cursor theme not installed: {name}"
+}
+
+// Hyprland needs a size to go with the theme
+const DEFAULT_SIZE: u32 = 24;
+
+pub struct Xcursor {
+    pub theme: Option<String>,
+    pub size: Option<u32>,
+    pub xresources: bool,
+}
+
+impl Xcursor {
+    pub fn get_installed_themes() -> Vec<String> {
+        let mut themes = vec![];
+        for icon_dir in xdg::icon_dirs() {
+            let Ok(entries) = fs::read_dir(icon_dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                // Icon themes share these folders, but only cursor themes have `cursors`
+                if entry.path().join("cursors").is_dir() {
+                    themes.push(entry.file_name().to_string_lossy().to_string());
+                }
+            }
+        }
+        themes.sort();
+        themes.dedup();
+        themes
+    }
+    /// Writes `Xcursor.theme` and `Xcursor.size` to their managed block in `~/.Xresources`.
+    pub fn write_xresources(
+        home: &Path,
+        theme: &Option<String>,
+        size: &Option<u32>,
+    ) -> io::Result<()> {
+        let mut resources = String::new();
+        if let Some(theme) = theme {
+            resources.push_str(&format!("Xcursor.theme: {}\n", theme));
+        }
+        if let Some(size) = size {
+            resources.push_str(&format!("Xcursor.size: {}\n", size));
+        }
+        edit::file(&home.join(".Xresources"), |contents| {
+            block::replace(contents, &block::XRESOURCES, "xcursor", &resources)
+        })
+    }
+    fn home_dir() -> Result<PathBuf, XcursorError> {
+        dirs::home_dir().ok_or(XcursorError::MissingHome)
+    }
+}
+
+#[async_trait::async_trait]
+impl Connector for Xcursor {
+    fn new() -> Result<Self, Box<dyn Error + 'static>> {
+        Ok(Self {
+            theme: None,
+            size: None,
+            xresources: true,
+        })
+    }
+    fn verify(&self) -> Result<(), Box<dyn Error + 'static>> {
+        let _ = Xcursor::home_dir()?;
+        if let Some(theme) = &self.theme {
+            if !Xcursor::get_installed_themes().contains(theme) {
+                return Err(XcursorError::ThemeNotFound {
+                    name: theme.clone(),
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+    async fn apply(&self) -> Result<(), Box<dyn Error + 'static>> {
+        if self.theme.is_none() && self.size.is_none() {
+            return Ok(());
+        }
+        let home = Xcursor::home_dir()?;
+        let config_dir = dirs::config_dir().ok_or(XcursorError::MissingHome)?;
+        let size = self.size.map(|size| size.to_string());
+
+        // Apps that know nothing else fall back to the `default` icon theme
+        if let Some(theme) = &self.theme {
+            edit::file(
+                &home.join(".icons").join("default").join("index.theme"),
+                |contents| {
+                    let contents = ini::set(contents, Some("Icon Theme"), "Name", "Default");
+                    ini::set(&contents, Some("Icon Theme"), "Inherits", theme)
+                },
+            )?;
+        }
+
+        edit::file(
+            &config_dir.join("environment.d").join("univeme-cursor.conf"),
+            |contents| {
+                let mut contents = contents.to_string();
+                if let Some(theme) = &self.theme {
+                    contents = ini::set(&contents, None, "XCURSOR_THEME", theme);
+                }
+                if let Some(size) = &size {
+                    contents = ini::set(&contents, None, "XCURSOR_SIZE", size);
+                }
+                contents
+            },
+        )?;
+
+        if self.xresources {
+            Xcursor::write_xresources(&home, &self.theme, &self.size)?;
+            if env::var_os("DISPLAY").is_some() {
+                let xresources = home.join(".Xresources");
+                process::run("xrdb", &["-merge", &xresources.to_string_lossy()]);
+            }
+        }
+
+        for version in ["gtk-3.0", "gtk-4.0"] {
+            edit::file(&config_dir.join(version).join("settings.ini"), |contents| {
+                let mut contents = contents.to_string();
+                if let Some(theme) = &self.theme {
+                    contents =
+                        ini::set(&contents, Some("Settings"), "gtk-cursor-theme-name", theme);
+                }
+                if let Some(size) = &size {
+                    contents = ini::set(&contents, Some("Settings"), "gtk-cursor-theme-size", size);
+                }
+                contents
+            })?;
+        }
+
+        // These fail harmlessly when GNOME or Hyprland are not in use
+        if let Some(theme) = &self.theme {
+            process::run(
+                "gsettings",
+                &["set", "org.gnome.desktop.interface", "cursor-theme", theme],
+            );
+        }
+        if let Some(size) = &size {
+            process::run(
+                "gsettings",
+                &["set", "org.gnome.desktop.interface", "cursor-size", size],
+            );
+        }
+        if env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some() {
+            if let Some(theme) = &self.theme {
+                let size = self.size.unwrap_or(DEFAULT_SIZE).to_string();
+                process::run("hyprctl", &["setcursor", theme, &size]);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,99 @@
+/*
+  Xresources connector
+  Name: xresources
+  Controls: terminal colors and cursor theme in the X resource database
+
+  Config options:
+  - Palette: Option<Palette> - `*.foreground`, `*.background` and `*.color0` to `*.color15`. None = do not change
+  - Cursor theme: Option<String> - Name of an installed XCursor theme, as `Xcursor.theme`. None = do not change
+  - Cursor size: Option<u32> - Cursor size in pixels, as `Xcursor.size`. None = do not change
+  NOTE: The colors go to `~/.Xresources.d/univeme`, which `~/.Xresources` includes from a managed block.
+  `Xcursor.*` goes to the same block in `~/.Xresources` as the xcursor connector's, which skips it when
+  this connector is in use. Everything else in `~/.Xresources` is left alone. st, urxvt and xterm only
+  read the colors when they start.
+*/
+
+use std::{env, error::Error, path::PathBuf};
+
+use crate::{
+    edit::{self, block},
+    palette::Palette,
+    process,
+};
+
+use super::{xcursor::Xcursor, Connector};
+
+custom_error::custom_error! {pub XresourcesError
+    MissingHome = "could not find the home directory",
+    CursorThemeNotFound{name: String} = "cursor theme not installed: {name}"
+}
+
+pub struct Xresources {
+    pub palette: Option<Palette>,
+    pub cursor_theme: Option<String>,
+    pub cursor_size: Option<u32>,
+}
+
+impl Xresources {
+    fn home_dir() -> Result<PathBuf, XresourcesError> {
+        dirs::home_dir().ok_or(XresourcesError::MissingHome)
+    }
+    pub fn resources(palette: &Palette) -> String {
+        let mut resources = String::from("! Generated by univeme\n");
+        resources.push_str(&format!("*.foreground: {}\n", palette.foreground));
+        resources.push_str(&format!("*.background: {}\n", palette.background));
+        for (index, color) in palette.colors.iter().flatten().take(16).enumerate() {
+            resources.push_str(&format!("*.color{}: {}\n", index, color));
+        }
+        resources
+    }
+}
+
+#[async_trait::async_trait]
+impl Connector for Xresources {
+    fn new() -> Result<Self, Box<dyn Error + 'static>> {
+        Ok(Self {
+            palette: None,
+            cursor_theme: None,
+            cursor_size: None,
+        })
+    }
+    fn verify(&self) -> Result<(), Box<dyn Error + 'static>> {
+        let _ = Xresources::home_dir()?;
+        if let Some(cursor_theme) = &self.cursor_theme {
+            if !Xcursor::get_installed_themes().contains(cursor_theme) {
+                return Err(XresourcesError::CursorThemeNotFound {
+                    name: cursor_theme.clone(),
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+    async fn apply(&self) -> Result<(), Box<dyn Error + 'static>> {
+        if self.palette.is_none() && self.cursor_theme.is_none() && self.cursor_size.is_none() {
+            return Ok(());
+        }
+        let home = Xresources::home_dir()?;
+        let xresources = home.join(".Xresources");
+        if let Some(palette) = &self.palette {
+            let include = home.join(".Xresources.d").join("univeme");
+            edit::file(&include, |_| Xresources::resources(palette))?;
+            edit::file(&xresources, |contents| {
+                block::replace(
+                    contents,
+                    &block::XRESOURCES,
+                    "include",
+                    "#include \".Xresources.d/univeme\"",
+                )
+            })?;
+        }
+        if self.cursor_theme.is_some() || self.cursor_size.is_some() {
+            Xcursor::write_xresources(&home, &self.cursor_theme, &self.cursor_size)?;
+        }
+        if env::var_os("DISPLAY").is_some() {
+            process::run("xrdb", &["-merge", &xresources.to_string_lossy()]);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,199 @@
+/*
+  Zellij connector
+  Name: zellij
+  Controls: theme
+
+  Config options:
+  - Theme: Option<String> - Name of a built-in theme, or one defined in the themes folder or config.kdl. None = do not change
+  - Palette: Option<Palette> - Colors for a generated `univeme` theme, which gets selected. Wins over the theme. None = do not change
+  NOTE: Zellij watches config.kdl, so running sessions switch theme on their own.
+*/
+
+use std::{error::Error, fs, path::PathBuf};
+
+use crate::{
+    edit,
+    palette::{Color, Palette},
+    xdg,
+};
+
+use super::Connector;
+
+custom_error::custom_error! {pub ZellijError
+    MissingHome = "could not find the home folder",
+    ThemeNotFound{name: String} = "zellij theme not found: {name}"
+}
+
+/// Name of the theme generated from the preset palette.
+pub const PALETTE_THEME: &str = "univeme";
+
+/// Themes that ship with Zellij. Not every version has all of them.
+const BUILT_IN_THEMES: [&str; 20] = [
+    "default",
+    "ansi",
+    "catppuccin-frappe",
+    "catppuccin-latte",
+    "catppuccin-macchiato",
+    "catppuccin-mocha",
+    "dracula",
+    "everforest-dark",
+    "everforest-light",
+    "gruvbox-dark",
+    "gruvbox-light",
+    "kanagawa",
+    "nord",
+    "onedark",
+    "solarized-dark",
+    "solarized-light",
+    "tokyo-night",
+    "tokyo-night-dark",
+    "tokyo-night-light",
+    "tokyo-night-storm",
+];
+
+pub struct Zellij {
+    pub theme: Option<String>,
+    pub palette: Option<Palette>,
+}
+
+impl Zellij {
+    fn config_dir() -> Result<PathBuf, ZellijError> {
+        if let Some(dir) = std::env::var_os("ZELLIJ_CONFIG_DIR") {
+            return Ok(PathBuf::from(dir));
+        }
+        Ok(xdg::config_home()
+            .ok_or(ZellijError::MissingHome)?
+            .join("zellij"))
+    }
+    /// Names of the themes inside `themes { ... }` blocks in a KDL file.
+    pub fn theme_names(contents: &str) -> Vec<String> {
+        let mut names = vec![];
+        let mut depth = 0;
+        let mut in_themes = false;
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.starts_with("//") {
+                continue;
+            }
+            if line.ends_with('{') {
+                let name = line.trim_end_matches('{').trim();
+                if depth == 0 && name == "themes" {
+                    in_themes = true;
+                } else if depth == 1 && in_themes {
+                    names.push(name.trim_matches('"').to_string());
+                }
+            }
+            depth += line.matches('{').count();
+            depth = depth.saturating_sub(line.matches('}').count());
+            if depth == 0 {
+                in_themes = false;
+            }
+        }
+        names
+    }
+    pub fn get_themes() -> Vec<String> {
+        let mut themes = BUILT_IN_THEMES.map(str::to_string).to_vec();
+        let Ok(config_dir) = Zellij::config_dir() else {
+            return themes;
+        };
+        let mut files = vec![config_dir.join("config.kdl")];
+        if let Ok(entries) = fs::read_dir(config_dir.join("themes")) {
+            files.extend(entries.flatten().map(|entry| entry.path()));
+        }
+        for file in files {
+            if let Ok(contents) = fs::read_to_string(file) {
+                themes.extend(Zellij::theme_names(&contents));
+            }
+        }
+        themes.sort();
+        themes.dedup();
+        themes
+    }
+    pub fn generate_theme(palette: &Palette) -> String {
+        let color = |index: usize, fallback: Color| {
+            palette.role(&format!("color{}", index)).unwrap_or(fallback)
+        };
+        let accent = palette.accent;
+        let colors = [
+            ("fg", palette.foreground),
+            ("bg", palette.surface()),
+            ("black", palette.background),
+            ("red", color(1, accent)),
+            ("green", color(2, accent)),
+            ("yellow", color(3, accent)),
+            ("blue", color(4, accent)),
+            ("magenta", color(5, accent)),
+            ("cyan", color(6, accent)),
+            ("white", palette.foreground),
+            ("orange", accent),
+        ];
+        let mut theme = format!(
+            "// Generated by univeme from the preset palette\nthemes {{\n    {} {{\n",
+            PALETTE_THEME
+        );
+        for (name, color) in colors {
+            theme.push_str(&format!("        {} \"{}\"\n", name, color));
+        }
+        theme.push_str("    }\n}\n");
+        theme
+    }
+    /// Sets the top-level `theme` node in the contents of config.kdl, keeping everything else.
+    pub fn set_theme(contents: &str, theme: &str) -> String {
+        let node = format!("theme \"{}\"", theme);
+        let mut lines = contents.lines().map(str::to_string).collect::<Vec<_>>();
+        // Top-level nodes are the ones that are not indented
+        let existing = lines.iter().position(|line| {
+            line.strip_prefix("theme")
+                .is_some_and(|rest| rest.starts_with([' ', '\t']))
+        });
+        match existing {
+            Some(i) => lines[i] = node,
+            None => lines.push(node),
+        }
+        let mut output = lines.join("\n");
+        output.push('\n');
+        output
+    }
+}
+
+#[async_trait::async_trait]
+impl Connector for Zellij {
+    fn new() -> Result<Self, Box<dyn Error + 'static>> {
+        Ok(Self {
+            theme: None,
+            palette: None,
+        })
+    }
+    fn verify(&self) -> Result<(), Box<dyn Error + 'static>> {
+        let _ = Zellij::config_dir()?;
+        if let (Some(theme), None) = (&self.theme, &self.palette) {
+            if !Zellij::get_themes().contains(theme) {
+                return Err(ZellijError::ThemeNotFound {
+                    name: theme.clone(),
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+    async fn apply(&self) -> Result<(), Box<dyn Error + 'static>> {
+        let config_dir = Zellij::config_dir()?;
+        let theme = match (&self.palette, &self.theme) {
+            (Some(palette), _) => {
+                edit::file(
+                    &config_dir
+                        .join("themes")
+                        .join(format!("{}.kdl", PALETTE_THEME)),
+                    |_| Zellij::generate_theme(palette),
+                )?;
+                PALETTE_THEME
+            }
+            (None, Some(theme)) => theme.as_str(),
+            (None, None) => return Ok(()),
+        };
+        edit::file(&config_dir.join("config.kdl"), |contents| {
+            Zellij::set_theme(contents, theme)
+        })?;
+        Ok(())
+    }
+}
@@ -0,0 +1,122 @@
+/*
+  Managed blocks
+  Used for: files that belong to the user, where univeme only owns a delimited part
+
+  Everything outside the markers is left alone. Applying again replaces the block instead
+  of adding a second one.
+*/
+
+pub struct CommentStyle {
+    pub start: &'static str,
+    pub end: &'static str,
+}
+
+pub const CSS: CommentStyle = CommentStyle {
+    start: "/* ",
+    end: " */",
+};
+
+pub const HASH: CommentStyle = CommentStyle {
+    start: "# ",
+    end: "",
+};
+
+pub const JS: CommentStyle = CommentStyle {
+    start: "// ",
+    end: "",
+};
+
+pub const XRESOURCES: CommentStyle = CommentStyle {
+    start: "! ",
+    end: "",
+};
+
+/// Replaces the block named `id` in `contents` with `body`, appending it if there is none yet.
+/// Different ids can share a file.
+pub fn replace(contents: &str, style: &CommentStyle, id: &str, body: &str) -> String {
+    let begin = format!("{}BEGIN univeme {}{}", style.start, id, style.end);
+    let end = format!("{}END univeme {}{}", style.start, id, style.end);
+    let mut block = format!("{}\n{}", begin, body);
+    if !block.ends_with('\n') {
+        block.push('\n');
+    }
+    block.push_str(&end);
+    block.push('\n');
+    if let Some(start) = contents.find(&begin) {
+        if let Some(stop) = contents[start..].find(&end) {
+            let mut stop = start + stop + end.len();
+            if contents[stop..].starts_with('\n') {
+                stop += 1;
+            }
+            let mut output = contents.to_string();
+            output.replace_range(start..stop, &block);
+            return output;
+        }
+    }
+    let mut output = contents.to_string();
+    if !output.is_empty() && !output.ends_with('\n') {
+        output.push('\n');
+    }
+    output.push_str(&block);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn appends_a_new_block() {
+        assert_eq!(
+            replace("", &HASH, "colors", "a = 1"),
+            "# BEGIN univeme colors\na = 1\n# END univeme colors\n"
+        );
+        assert_eq!(
+            replace("mine\n", &CSS, "colors", "a {}\n"),
+            "mine\n/* BEGIN univeme colors */\na {}\n/* END univeme colors */\n"
+        );
+        // A last line without a newline is finished first
+        assert_eq!(
+            replace("mine", &JS, "x", "y"),
+            "mine\n// BEGIN univeme x\ny\n// END univeme x\n"
+        );
+    }
+
+    #[test]
+    fn replaces_an_existing_block() {
+        let contents =
+            "before\n\n# BEGIN univeme colors\nold\nlines\n# END univeme colors\nafter\n";
+        assert_eq!(
+            replace(contents, &HASH, "colors", "new\n"),
+            "before\n\n# BEGIN univeme colors\nnew\n# END univeme colors\nafter\n"
+        );
+    }
+
+    #[test]
+    fn unchanged_blocks_leave_the_file_as_it_was() {
+        let contents = "! mine\r\nURxvt.font: x\n\n! BEGIN univeme include\n#include \"a\"\n! END univeme include\n! trailing";
+        assert_eq!(
+            replace(contents, &XRESOURCES, "include", "#include \"a\""),
+            contents
+        );
+    }
+
+    #[test]
+    fn blocks_with_other_ids_are_separate() {
+        let contents = replace("", &XRESOURCES, "include", "a");
+        let contents = replace(&contents, &XRESOURCES, "xcursor", "b");
+        let contents = replace(&contents, &XRESOURCES, "include", "c");
+        assert_eq!(
+            contents,
+            "! BEGIN univeme include\nc\n! END univeme include\n! BEGIN univeme xcursor\nb\n! END univeme xcursor\n"
+        );
+    }
+
+    #[test]
+    fn a_block_without_an_end_is_not_replaced() {
+        assert_eq!(
+            replace("# BEGIN univeme x\nold\n", &HASH, "x", "new"),
+            "# BEGIN univeme x\nold\n# BEGIN univeme x\nnew\n# END univeme x\n"
+        );
+    }
+}
@@ -0,0 +1,188 @@
+/*
+  INI editing
+  Used for: GTK settings.ini, gtkrc, and other `key=value` files
+
+  Lines that are not touched are written back exactly as they were read, including
+  comments and blank lines. Keys before the first `[section]` header belong to `None`.
+*/
+
+fn section_header(line: &str) -> Option<&str> {
+    line.trim()
+        .strip_prefix('[')
+        .and_then(|line| line.strip_suffix(']'))
+        .map(str::trim)
+}
+
+fn is_comment(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with('#') || line.starts_with(';')
+}
+
+/// Reads the value of `key` in `section`, with surrounding whitespace removed.
+pub fn get(contents: &str, section: Option<&str>, key: &str) -> Option<String> {
+    let mut current = None;
+    for line in contents.lines() {
+        if let Some(name) = section_header(line) {
+            current = Some(name);
+            continue;
+        }
+        if current != section || is_comment(line) {
+            continue;
+        }
+        if let Some((name, value)) = line.split_once('=') {
+            if name.trim() == key {
+                return Some(value.trim().to_string());
+            }
+        }
+    }
+    None
+}
+
+/// Names of every `[section]`, in the order they appear.
+pub fn sections(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .filter_map(section_header)
+        .map(str::to_string)
+        .collect()
+}
+
+/// Sets `key` in `section` to `value`. Existing keys keep their spacing around `=`, new keys
+/// are added at the end of their section (creating it if needed) in the file's spacing style.
+pub fn set(contents: &str, section: Option<&str>, key: &str, value: &str) -> String {
+    let mut lines = contents.lines().map(str::to_string).collect::<Vec<_>>();
+    let spaced = lines.iter().any(|line| line.contains(" = "));
+    let mut current: Option<String> = None;
+    let mut last_in_section = None;
+    for (i, line) in lines.iter_mut().enumerate() {
+        if let Some(name) = section_header(line) {
+            current = Some(name.to_string());
+            if current.as_deref() == section {
+                last_in_section = Some(i);
+            }
+            continue;
+        }
+        if current.as_deref() != section {
+            continue;
+        }
+        if !line.trim().is_empty() {
+            last_in_section = Some(i);
+        }
+        if is_comment(line) {
+            continue;
+        }
+        if let Some((name, after)) = line.split_once('=') {
+            if name.trim() == key {
+                let keep = name.len() + 1 + (after.len() - after.trim_start().len());
+                line.truncate(keep);
+                line.push_str(value);
+                return join(lines);
+            }
+        }
+    }
+    let entry = if spaced {
+        format!("{} = {}", key, value)
+    } else {
+        format!("{}={}", key, value)
+    };
+    match (last_in_section, section) {
+        (Some(i), _) => lines.insert(i + 1, entry),
+        (None, None) => lines.insert(0, entry),
+        (None, Some(section)) => {
+            if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push(format!("[{}]", section));
+            lines.push(entry);
+        }
+    }
+    join(lines)
+}
+
+fn join(lines: Vec<String>) -> String {
+    let mut output = lines.join("\n");
+    output.push('\n');
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SETTINGS: &str = "# GTK settings\n\n[Settings]\ngtk-theme-name = Adwaita\n; old\n# gtk-font-name = Sans\n\n[Other]\nkey=value\n";
+
+    #[test]
+    fn sets_a_key_in_an_existing_section() {
+        assert_eq!(
+            set(SETTINGS, Some("Settings"), "gtk-theme-name", "Breeze"),
+            SETTINGS.replace("Adwaita", "Breeze")
+        );
+        assert_eq!(
+            set(SETTINGS, Some("Settings"), "gtk-font-name", "Inter 11"),
+            SETTINGS.replace(
+                "# gtk-font-name = Sans\n",
+                "# gtk-font-name = Sans\ngtk-font-name = Inter 11\n"
+            )
+        );
+        assert_eq!(
+            get(SETTINGS, Some("Settings"), "gtk-theme-name"),
+            Some("Adwaita".to_string())
+        );
+        // Commented-out keys do not count
+        assert_eq!(get(SETTINGS, Some("Settings"), "gtk-font-name"), None);
+    }
+
+    #[test]
+    fn keeps_the_spacing_around_equals() {
+        assert_eq!(set("a =  1\nb=2\n", None, "a", "3"), "a =  3\nb=2\n");
+        assert_eq!(set("b=2\n", None, "a", "1"), "b=2\na=1\n");
+        assert_eq!(set("b = 2\n", None, "a", "1"), "b = 2\na = 1\n");
+    }
+
+    #[test]
+    fn adds_a_missing_section() {
+        assert_eq!(
+            set(SETTINGS, Some("New"), "a", "1"),
+            format!("{}\n[New]\na = 1\n", SETTINGS)
+        );
+        assert_eq!(set("", Some("Settings"), "a", "1"), "[Settings]\na=1\n");
+        assert_eq!(
+            sections(&set(SETTINGS, Some("New"), "a", "1")),
+            ["Settings", "Other", "New"]
+        );
+    }
+
+    #[test]
+    fn keys_without_a_section_stay_before_the_first_header() {
+        assert_eq!(
+            set("# top\n[Section]\na = 1\n", None, "palette", "\"x\""),
+            "# top\npalette = \"x\"\n[Section]\na = 1\n"
+        );
+        assert_eq!(
+            set(
+                "palette = 'y'\n\n[Section]\npalette = 1\n",
+                None,
+                "palette",
+                "\"x\""
+            ),
+            "palette = \"x\"\n\n[Section]\npalette = 1\n"
+        );
+        assert_eq!(get("a=1\n[S]\na=2\n", None, "a"), Some("1".to_string()));
+        assert_eq!(
+            get("a=1\n[S]\na=2\n", Some("S"), "a"),
+            Some("2".to_string())
+        );
+    }
+
+    #[test]
+    fn only_the_first_duplicate_key_changes() {
+        assert_eq!(
+            set("[S]\na=1\na=2\n", Some("S"), "a", "3"),
+            "[S]\na=3\na=2\n"
+        );
+        assert_eq!(
+            get("[S]\na=1\na=2\n", Some("S"), "a"),
+            Some("1".to_string())
+        );
+    }
+}
@@ -0,0 +1,344 @@
+/*
+  JSONC editing
+  Used for: VS Code style settings files
+
+  Only the top-level object is understood. Everything else (comments, nested values,
+  whitespace, trailing commas) is kept exactly as it was written.
+*/
+
+use std::ops::Range;
+
+custom_error::custom_error! {pub JsoncError
+    Malformed = "malformed JSONC document",
+    NotAnObject = "JSONC document is not an object"
+}
+
+struct Member {
+    key: String,
+    value: Range<usize>,
+    indent: String,
+}
+
+struct Scanner<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Scanner<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+    // Skips whitespace, `// line` and `/* block */` comments
+    fn skip_trivia(&mut self) -> Result<(), JsoncError> {
+        loop {
+            match (self.peek(), self.bytes.get(self.pos + 1).copied()) {
+                (Some(b' ' | b'\t' | b'\r' | b'\n'), _) => self.pos += 1,
+                (Some(b'/'), Some(b'/')) => {
+                    while !matches!(self.peek(), None | Some(b'\n')) {
+                        self.pos += 1;
+                    }
+                }
+                (Some(b'/'), Some(b'*')) => {
+                    self.pos += 2;
+                    loop {
+                        match self.peek() {
+                            None => return Err(JsoncError::Malformed),
+                            Some(b'*') if self.bytes.get(self.pos + 1) == Some(&b'/') => {
+                                self.pos += 2;
+                                break;
+                            }
+                            _ => self.pos += 1,
+                        }
+                    }
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+    fn string(&mut self) -> Result<String, JsoncError> {
+        let start = self.pos;
+        if self.peek() != Some(b'"') {
+            return Err(JsoncError::Malformed);
+        }
+        self.pos += 1;
+        loop {
+            match self.peek() {
+                None => return Err(JsoncError::Malformed),
+                Some(b'\\') => self.pos += 2,
+                Some(b'"') => {
+                    self.pos += 1;
+                    break;
+                }
+                _ => self.pos += 1,
+            }
+        }
+        let raw =
+            std::str::from_utf8(&self.bytes[start..self.pos]).map_err(|_| JsoncError::Malformed)?;
+        serde_json::from_str::<String>(raw).map_err(|_| JsoncError::Malformed)
+    }
+    // Moves past one value of any kind, returning where it ended
+    fn value(&mut self) -> Result<usize, JsoncError> {
+        match self.peek() {
+            None => Err(JsoncError::Malformed),
+            Some(b'"') => {
+                self.string()?;
+                Ok(self.pos)
+            }
+            Some(b'{' | b'[') => {
+                let mut depth = 0usize;
+                loop {
+                    self.skip_trivia()?;
+                    match self.peek() {
+                        None => return Err(JsoncError::Malformed),
+                        Some(b'"') => {
+                            self.string()?;
+                        }
+                        Some(b'{' | b'[') => {
+                            depth += 1;
+                            self.pos += 1;
+                        }
+                        Some(b'}' | b']') => {
+                            depth -= 1;
+                            self.pos += 1;
+                            if depth == 0 {
+                                return Ok(self.pos);
+                            }
+                        }
+                        Some(_) => self.pos += 1,
+                    }
+                }
+            }
+            Some(_) => {
+                // Numbers, `true`, `false` and `null` run until the next delimiter
+                let start = self.pos;
+                while !matches!(
+                    self.peek(),
+                    None | Some(b',' | b'}' | b']' | b' ' | b'\t' | b'\r' | b'\n' | b'/')
+                ) {
+                    self.pos += 1;
+                }
+                if self.pos == start {
+                    Err(JsoncError::Malformed)
+                } else {
+                    Ok(self.pos)
+                }
+            }
+        }
+    }
+}
+
+fn line_indent(contents: &str, pos: usize) -> String {
+    let line_start = contents[..pos].rfind('\n').map_or(0, |i| i + 1);
+    contents[line_start..pos]
+        .chars()
+        .take_while(|c| *c == ' ' || *c == '\t')
+        .collect()
+}
+
+// Returns the members of the top-level object and the position of its closing brace
+fn members(contents: &str) -> Result<(Vec<Member>, usize), JsoncError> {
+    let mut scanner = Scanner {
+        bytes: contents.as_bytes(),
+        pos: 0,
+    };
+    scanner.skip_trivia()?;
+    if scanner.peek() != Some(b'{') {
+        return Err(JsoncError::NotAnObject);
+    }
+    scanner.pos += 1;
+    let mut members = vec![];
+    loop {
+        scanner.skip_trivia()?;
+        match scanner.peek() {
+            Some(b'}') => return Ok((members, scanner.pos)),
+            Some(b'"') => {
+                let indent = line_indent(contents, scanner.pos);
+                let key = scanner.string()?;
+                scanner.skip_trivia()?;
+                if scanner.peek() != Some(b':') {
+                    return Err(JsoncError::Malformed);
+                }
+                scanner.pos += 1;
+                scanner.skip_trivia()?;
+                let start = scanner.pos;
+                let end = scanner.value()?;
+                members.push(Member {
+                    key,
+                    value: start..end,
+                    indent,
+                });
+                scanner.skip_trivia()?;
+                if scanner.peek() == Some(b',') {
+                    scanner.pos += 1;
+                }
+            }
+            _ => return Err(JsoncError::Malformed),
+        }
+    }
+}
+
+/// Sets `key` in the top-level object to `value`, adding it after the last member if it
+/// does not exist yet. An empty document is treated as an empty object.
+pub fn set_top_level(
+    contents: &str,
+    key: &str,
+    value: &serde_json::Value,
+) -> Result<String, JsoncError> {
+    let value = serde_json::to_string(value).map_err(|_| JsoncError::Malformed)?;
+    if contents.trim().is_empty() {
+        return Ok(format!(
+            "{{\n    {}: {}\n}}\n",
+            serde_json::Value::from(key),
+            value
+        ));
+    }
+    let (members, close) = members(contents)?;
+    let mut output = contents.to_string();
+    match members.iter().find(|member| member.key == key) {
+        Some(member) => {
+            output.replace_range(member.value.clone(), &value);
+        }
+        None => match members.last() {
+            Some(last) => {
+                let indent = &members[0].indent;
+                output.insert_str(
+                    last.value.end,
+                    &format!(",\n{}{}: {}", indent, serde_json::Value::from(key), value),
+                );
+            }
+            None => {
+                let outer = line_indent(contents, close);
+                output.insert_str(
+                    close,
+                    &format!(
+                        "\n{}    {}: {}\n{}",
+                        outer,
+                        serde_json::Value::from(key),
+                        value,
+                        outer
+                    ),
+                );
+            }
+        },
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn empty_file_becomes_an_object() {
+        assert_eq!(
+            set_top_level("", "a", &json!(1)).unwrap(),
+            "{\n    \"a\": 1\n}\n"
+        );
+        assert_eq!(
+            set_top_level(" \n", "a", &json!(1)).unwrap(),
+            "{\n    \"a\": 1\n}\n"
+        );
+    }
+
+    #[test]
+    fn empty_object_gets_the_first_key() {
+        assert_eq!(
+            set_top_level("{}", "a", &json!("x")).unwrap(),
+            "{\n    \"a\": \"x\"\n}"
+        );
+    }
+
+    #[test]
+    fn replaces_an_existing_key() {
+        let contents = "{\n  \"a\": 1,\n  \"b\": [1, 2],\n  \"c\": true\n}\n";
+        assert_eq!(
+            set_top_level(contents, "b", &json!({"x": 1})).unwrap(),
+            "{\n  \"a\": 1,\n  \"b\": {\"x\":1},\n  \"c\": true\n}\n"
+        );
+    }
+
+    #[test]
+    fn appends_a_new_key_with_the_same_indent() {
+        let contents = "{\n\t\"a\": 1\n}\n";
+        assert_eq!(
+            set_top_level(contents, "b", &json!(2)).unwrap(),
+            "{\n\t\"a\": 1,\n\t\"b\": 2\n}\n"
+        );
+    }
+
+    #[test]
+    fn keeps_trailing_commas() {
+        let contents = "{\n    \"a\": 1,\n}\n";
+        assert_eq!(
+            set_top_level(contents, "b", &json!(2)).unwrap(),
+            "{\n    \"a\": 1,\n    \"b\": 2,\n}\n"
+        );
+        assert_eq!(
+            set_top_level(contents, "a", &json!(3)).unwrap(),
+            "{\n    \"a\": 3,\n}\n"
+        );
+    }
+
+    #[test]
+    fn keeps_comments() {
+        let contents =
+            "// settings\n{\n    /* theme */ \"a\": 1, // old\n    // \"b\": 2,\n    \"c\": 3\n}\n";
+        assert_eq!(
+            set_top_level(contents, "a", &json!(4)).unwrap(),
+            "// settings\n{\n    /* theme */ \"a\": 4, // old\n    // \"b\": 2,\n    \"c\": 3\n}\n"
+        );
+        // The commented-out key does not count
+        assert_eq!(
+            set_top_level(contents, "b", &json!(5)).unwrap(),
+            "// settings\n{\n    /* theme */ \"a\": 1, // old\n    // \"b\": 2,\n    \"c\": 3,\n    \"b\": 5\n}\n"
+        );
+    }
+
+    #[test]
+    fn strings_can_hold_comment_and_bracket_characters() {
+        let contents = r#"{
+    "url": "https://example.com/{x}",
+    "quote": "say \"}\" // not a comment",
+    "a": 1
+}"#;
+        assert_eq!(
+            set_top_level(contents, "a", &json!(2)).unwrap(),
+            contents.replace("\"a\": 1", "\"a\": 2")
+        );
+    }
+
+    #[test]
+    fn nested_values_are_skipped_whole() {
+        let contents = r#"{
+    "nested": {"a": {"b": [1, {"a": 2}]}, "s": "]"},
+    "list": [[], {}, "{"],
+    "a": 1
+}"#;
+        assert_eq!(
+            set_top_level(contents, "a", &json!(null)).unwrap(),
+            contents.replace("\"a\": 1", "\"a\": null")
+        );
+        // Keys of nested objects are not top-level keys
+        assert!(set_top_level(contents, "b", &json!(1))
+            .unwrap()
+            .ends_with("\"a\": 1,\n    \"b\": 1\n}"));
+    }
+
+    #[test]
+    fn rejects_other_documents() {
+        assert!(matches!(
+            set_top_level("[1]", "a", &json!(1)),
+            Err(JsoncError::NotAnObject)
+        ));
+        assert!(matches!(
+            set_top_level("{\"a\": 1", "a", &json!(1)),
+            Err(JsoncError::Malformed)
+        ));
+        assert!(matches!(
+            set_top_level("{/* open", "a", &json!(1)),
+            Err(JsoncError::Malformed)
+        ));
+    }
+}
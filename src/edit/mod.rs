@@ -0,0 +1,24 @@
+use std::{fs, io, path::Path};
+
+pub mod block;
+pub mod ini;
+pub mod jsonc;
+
+/// Reads `path`, or nothing if it does not exist yet. Any other error is returned, so a file that
+/// cannot be read (or is not UTF-8) is never taken for an empty one and overwritten.
+pub fn read(path: &Path) -> io::Result<String> {
+    match fs::read_to_string(path) {
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(String::new()),
+        result => result,
+    }
+}
+
+/// Reads `path` (empty if it does not exist yet), passes it through `edit` and writes the
+/// result back, creating parent folders as needed.
+pub fn file(path: &Path, edit: impl FnOnce(&str) -> String) -> io::Result<()> {
+    let contents = read(path)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, edit(&contents))
+}
@@ -1,2 +1,6 @@
 pub mod connectors;
+pub mod edit;
+pub mod palette;
+pub mod process;
 pub mod toml;
+pub mod xdg;
@@ -1,106 +1,569 @@
-use clap::Parser;
-use reqwest::Url;
+use clap::{Parser, Subcommand};
+use reqwest::{Method, Url};
 
-#[cfg(windows)]
-use std::collections::HashMap;
-
-use std::{fs, str::FromStr};
+use std::{collections::HashMap, fs, path::PathBuf, str::FromStr, sync::Arc};
 
 use univeme::connectors::pprefox::Pprefox;
 
 #[cfg(windows)]
-use univeme::connectors::windows::{CursorScheme, Windows};
+use univeme::connectors::registry_backend::WindowsRegistry;
 
 #[cfg(windows)]
-use univeme::connectors::wpeng::Wpeng;
+use univeme::connectors::{
+    pprefox::Instance,
+    wpeng::Wpeng,
+    wpeng_control::{self, Playback},
+    wpeng_library::WallpaperId,
+    wpeng_monitor::{self, MonitorTarget},
+};
+
+#[cfg(unix)]
+use univeme::connectors::{
+    bar::Bar, desktop::Desktop, gtk::Gtk, launcher::Launcher, notifications::Notifications, qt::Qt,
+    tmux::Tmux, xcursor::Xcursor, xresources::Xresources, zellij::Zellij,
+};
 
 use univeme::{
-    connectors::{ledfx::Ledfx, Connector},
+    connectors::{
+        chromium::{Chromium, ColorScheme},
+        cli::Cli,
+        cursor_pack,
+        discord::{ClientMod, Discord, DiscordTheme},
+        firefox::Firefox,
+        http::{Http, Lookup},
+        ledfx::Ledfx,
+        mozilla,
+        obsidian::Obsidian,
+        registry_backend::{self, RegFileWriter, RegistryBackend},
+        spicetify::Spicetify,
+        template::{self, Template},
+        thunderbird::Thunderbird,
+        vscode::{Flavor, Vscode},
+        windows::{CursorScheme, Windows, WindowsError, WINDOWS_DEFAULT_SCHEME},
+        Connector,
+    },
+    palette::Palette,
     toml::Config,
 };
 
 pub enum ConnectorConfig {
     Pprefox(Pprefox),
+    Firefox(Firefox),
+    Chromium(Chromium),
+    Thunderbird(Thunderbird),
+    Discord(Discord),
+    Spicetify(Spicetify),
+    Obsidian(Obsidian),
+    Cli(Cli),
+    Template(Template),
+    Http(Http),
     #[cfg(windows)]
     Windows(Windows),
     #[cfg(windows)]
     Wpeng(Wpeng),
     Ledfx(Ledfx),
+    Vscode(Vscode),
+    #[cfg(unix)]
+    Gtk(Gtk),
+    #[cfg(unix)]
+    Qt(Qt),
+    #[cfg(unix)]
+    Xcursor(Xcursor),
+    #[cfg(unix)]
+    Tmux(Tmux),
+    #[cfg(unix)]
+    Zellij(Zellij),
+    #[cfg(unix)]
+    Notifications(Notifications),
+    #[cfg(unix)]
+    Launcher(Launcher),
+    #[cfg(unix)]
+    Bar(Bar),
+    #[cfg(unix)]
+    Xresources(Xresources),
+    #[cfg(unix)]
+    Desktop(Desktop),
+}
+
+custom_error::custom_error! {MainError
+    UnknownConnector{name: String} = "cannot list themes for connector: {name}"
 }
 
 /// the universal theme tool
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
     /// Path to config toml
-    config: String,
+    #[arg(required = true)]
+    config: Option<String>,
+    #[command(subcommand)]
+    command: Option<Commands>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// List the themes a connector can select
+    List {
+        /// Connector name, as used in the config
+        connector: String,
+    },
+    /// Write the registry changes a config's `[[windows]]` sections would make to a .reg file,
+    /// without making them
+    ExportReg {
+        /// Path to config toml
+        config: String,
+        /// Path of the .reg file to write
+        output: String,
+    },
+}
+
+async fn list(connector: &str) -> Result<(), Box<dyn std::error::Error + 'static>> {
+    let mut available: Vec<String> = match connector {
+        #[cfg(windows)]
+        "windows" => {
+            let registry = Windows::new()?;
+            let mut schemes = registry.get_system_cursor_schemes().unwrap_or_default();
+            schemes.extend(registry.get_user_cursor_schemes().unwrap_or_default());
+            schemes.push(WINDOWS_DEFAULT_SCHEME.to_string());
+            schemes
+        }
+        #[cfg(windows)]
+        "wpeng" => Wpeng::new()?.get_wallpaper_options()?.into_keys().collect(),
+        "firefox" => Firefox::get_profiles()
+            .iter()
+            .flat_map(|profile| mozilla::installed_themes(&profile.path))
+            .collect(),
+        "thunderbird" => Thunderbird::get_profiles()
+            .iter()
+            .flat_map(|profile| mozilla::installed_themes(&profile.path))
+            .collect(),
+        "bat" | "delta" => Cli::get_bat_themes().unwrap_or_default(),
+        "btop" => Cli::get_btop_themes(),
+        "obsidian" => Obsidian::get_vaults()
+            .iter()
+            .flat_map(|vault| Obsidian::get_installed_themes(vault))
+            .collect(),
+        "spicetify" => Spicetify::new()?.get_themes(),
+        "vscode" => {
+            let installed = Vscode::new()?.get_installed_themes();
+            [
+                installed.color_themes,
+                installed.icon_themes,
+                installed.product_icon_themes,
+            ]
+            .concat()
+        }
+        #[cfg(unix)]
+        "gtk" => Gtk::get_installed_themes(),
+        #[cfg(unix)]
+        "xcursor" => Xcursor::get_installed_themes(),
+        #[cfg(windows)]
+        "fonts" => Windows::new()?.get_installed_fonts(),
+        #[cfg(unix)]
+        "fonts" => Desktop::get_installed_fonts().unwrap_or_default(),
+        #[cfg(unix)]
+        "icons" => univeme::xdg::icon_themes(),
+        #[cfg(unix)]
+        "tmux" => Tmux::get_themes(),
+        #[cfg(unix)]
+        "zellij" => Zellij::get_themes(),
+        #[cfg(unix)]
+        // Labelled with the option each name is for, which also keeps them grouped once sorted
+        "qt" => Qt::get_kvantum_themes()
+            .into_iter()
+            .map(|theme| format!("kvantum_theme: {}", theme))
+            .chain(Qt::get_color_schemes().into_iter().map(|scheme| {
+                let tools = Qt::color_scheme_tools(&scheme).join(", ");
+                format!("color_scheme: {} ({})", scheme, tools)
+            }))
+            .chain(
+                univeme::xdg::icon_themes()
+                    .into_iter()
+                    .map(|theme| format!("icon_theme: {}", theme)),
+            )
+            .collect(),
+        _ => {
+            return Err(MainError::UnknownConnector {
+                name: connector.to_string(),
+            }
+            .into())
+        }
+    };
+    available.sort();
+    available.dedup();
+    for item in available {
+        println!("{}", item);
+    }
+    Ok(())
+}
+
+/// Builds the `[[windows]]` connectors, each with its own handle from `registry`, so they
+/// can run against something other than the real registry.
+fn windows_connectors(
+    windows: Option<Vec<univeme::toml::Windows>>,
+    cursor_scheme: &Option<String>,
+    ui_font: &Option<String>,
+    palette: &Option<Palette>,
+    registry: impl Fn() -> Box<dyn RegistryBackend>,
+) -> Result<Vec<Windows>, Box<dyn std::error::Error + 'static>> {
+    // A top-level `cursor_scheme` or UI font applies even without a `[[windows]]` section
+    let windows = match windows {
+        None if cursor_scheme.is_some() || ui_font.is_some() => {
+            vec![univeme::toml::Windows::default()]
+        }
+        windows => windows.unwrap_or_default(),
+    };
+    let lookup = Windows::with_registry(registry());
+    if let Some(ui_font) = ui_font {
+        if !Windows::has_font(&lookup.get_installed_fonts(), ui_font) {
+            return Err(WindowsError::FontNotFound {
+                name: ui_font.clone(),
+            }
+            .into());
+        }
+    }
+    let mut available_cursors = HashMap::new();
+    available_cursors.insert(
+        WINDOWS_DEFAULT_SCHEME.to_string(),
+        CursorScheme::WindowsDefault,
+    );
+    let system_cursors = lookup.get_system_cursor_schemes();
+    // Exporting a .reg file where there is no registry (on Linux) only knows the default and packs
+    let has_registry = system_cursors.is_ok();
+    for system_cursor in system_cursors.unwrap_or_default() {
+        available_cursors.insert(
+            system_cursor.clone(),
+            CursorScheme::SystemScheme(system_cursor),
+        );
+    }
+    for user_cursor in lookup.get_user_cursor_schemes().unwrap_or_default() {
+        available_cursors.insert(user_cursor.clone(), CursorScheme::UserScheme(user_cursor));
+    }
+    let mut connectors = vec![];
+    for windows in windows {
+        let mut connector = Windows::with_registry(registry());
+        // A pack is only installed when applying, but its scheme can be selected already
+        let mut pack_scheme = None;
+        if let Some(pack) = windows.cursor_pack {
+            let pack = PathBuf::from(pack);
+            let scheme_name = cursor_pack::read_pack(&pack)?.scheme_name;
+            available_cursors.insert(
+                scheme_name.clone(),
+                CursorScheme::UserScheme(scheme_name.clone()),
+            );
+            pack_scheme = Some(scheme_name);
+            connector.cursor_pack = Some(pack);
+        }
+        let cursor_scheme = match windows
+            .cursor_scheme
+            .or(pack_scheme)
+            .or_else(|| cursor_scheme.clone())
+        {
+            None => None,
+            Some(cursor_scheme) => match available_cursors.get(&cursor_scheme) {
+                None if has_registry => {
+                    return Err(WindowsError::SchemeNotFound {
+                        name: cursor_scheme,
+                    }
+                    .into())
+                }
+                None => {
+                    return Err(WindowsError::SchemeNeedsRegistry {
+                        name: cursor_scheme,
+                    }
+                    .into())
+                }
+                Some(scheme_with_type) => Some(scheme_with_type.clone()),
+            },
+        };
+        connector.cursor_scheme = cursor_scheme;
+        connector.theme_path = windows.theme_path.map(PathBuf::from);
+        connector.light_mode = windows
+            .light_mode
+            .or(palette.as_ref().map(|palette| palette.is_light()));
+        connector.enable_color_prevalence = windows.enable_color_prevalence;
+        connector.accent_color = windows.accent_color;
+        connector.transparency = windows.transparency;
+        connector.ui_font = ui_font.clone();
+        connectors.push(connector);
+    }
+    Ok(connectors)
+}
+
+/// Writes the registry changes the `[[windows]]` sections of a preset would make to a .reg file.
+async fn export_reg(
+    config: Config,
+    output: &str,
+) -> Result<(), Box<dyn std::error::Error + 'static>> {
+    let writer = Arc::new(RegFileWriter::new(registry_backend::system_registry()));
+    for connector in windows_connectors(
+        config.windows,
+        &config.cursor_scheme,
+        &config.fonts.as_ref().and_then(|fonts| fonts.ui.clone()),
+        &config.palette,
+        || Box::new(writer.clone()),
+    )? {
+        connector.apply().await?;
+    }
+    fs::write(output, writer.to_bytes())?;
+    Ok(())
+}
+
+fn read_config(path: &str) -> Config {
+    let toml_contents = fs::read_to_string(path).expect("Config path does not exist.");
+    toml::from_str(&toml_contents).unwrap()
 }
 
 #[tokio::main]
 pub async fn main() -> Result<(), Box<dyn std::error::Error + 'static>> {
     let args = Args::parse();
-    let toml_contents = fs::read_to_string(args.config).expect("Config path does not exist.");
-    let config: Config = toml::from_str(&toml_contents).unwrap();
+    match args.command {
+        Some(Commands::List { connector }) => return list(&connector).await,
+        Some(Commands::ExportReg { config, output }) => {
+            return export_reg(read_config(&config), &output).await
+        }
+        None => (),
+    }
+    let config = read_config(&args.config.expect("Config path is required."));
     // We will loop through and apply each one soon
     let mut connectors: Vec<ConnectorConfig> = vec![];
     #[cfg(windows)]
     for pprefox in config.pprefox.unwrap_or_default() {
-        // Parse endpoint to URL here so we can use Try
-        let endpoint = match pprefox.endpoint {
-            None => None,
-            Some(endpoint) => Some(Url::from_str(&endpoint)?),
+        let mut connector = Pprefox::new()?;
+        let instances = match pprefox.endpoint {
+            // Parse endpoint to URL here so we can use Try
+            Some(endpoint) => vec![Instance {
+                profile: endpoint.clone(),
+                endpoint: Url::from_str(&endpoint)?,
+            }],
+            None => {
+                let ports = match pprefox.ports {
+                    Some([first, last]) => first..=last,
+                    None => Pprefox::DEFAULT_PORTS,
+                };
+                Pprefox::discover(ports).await?
+            }
         };
-        let mut connector = Pprefox {
-            endpoint,
-            theme_id: None,
+        connector.instances = Pprefox::select(instances, &pprefox.profiles)?;
+        connector.theme_name = Some(pprefox.theme_name);
+        for profile in connector.resolve_theme().await? {
+            eprintln!(
+                "pprefox: skipping {}, its pprefox host did not answer",
+                profile
+            );
+        }
+        connector.verify()?;
+        connectors.push(ConnectorConfig::Pprefox(connector));
+    }
+    for firefox in config.firefox.unwrap_or_default() {
+        let mut connector = Firefox::new()?;
+        connector.profiles = mozilla::select(connector.profiles, &firefox.profiles)?;
+        if firefox.user_chrome.unwrap_or(false) {
+            connector.palette = config.palette.clone();
+        }
+        connector.dark = firefox
+            .dark
+            .or(config.palette.as_ref().map(|palette| !palette.is_light()));
+        connector.theme_id = firefox.theme_id;
+        connector.verify()?;
+        connectors.push(ConnectorConfig::Firefox(connector));
+    }
+    for thunderbird in config.thunderbird.unwrap_or_default() {
+        let mut connector = Thunderbird::new()?;
+        connector.profiles = mozilla::select(connector.profiles, &thunderbird.profiles)?;
+        if thunderbird.user_chrome.unwrap_or(false) {
+            connector.palette = config.palette.clone();
+        }
+        connector.dark = thunderbird
+            .dark
+            .or(config.palette.as_ref().map(|palette| !palette.is_light()));
+        connector.theme_id = thunderbird.theme_id;
+        connector.verify()?;
+        connectors.push(ConnectorConfig::Thunderbird(connector));
+    }
+    for discord in config.discord.unwrap_or_default() {
+        let mut connector = Discord::new()?;
+        let client_mods = match discord.client_mods {
+            Some(client_mods) => Some(
+                client_mods
+                    .iter()
+                    .map(|client_mod| ClientMod::from_str(client_mod))
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+            None => None,
         };
-        let themes = connector.get_available_themes().await?;
-        match themes.get(&pprefox.theme_name) {
-            None => panic!("Firefox theme not found: {}", pprefox.theme_name),
-            Some(id) => {
-                connector.theme_id = Some(id.to_string());
+        connector.installs = Discord::select(connector.installs, &client_mods)?;
+        connector.theme = match (discord.theme_file, &config.palette) {
+            (Some(theme_file), _) => Some(DiscordTheme::File(PathBuf::from(theme_file))),
+            (None, Some(palette)) if discord.palette_theme.unwrap_or(false) => {
+                Some(DiscordTheme::Palette {
+                    palette: palette.clone(),
+                    name: config.name.clone().unwrap_or_else(|| "univeme".to_string()),
+                    author: config
+                        .author
+                        .clone()
+                        .unwrap_or_else(|| "univeme".to_string()),
+                })
             }
+            _ => None,
+        };
+        connector.verify()?;
+        connectors.push(ConnectorConfig::Discord(connector));
+    }
+    for spicetify in config.spicetify.unwrap_or_default() {
+        let mut connector = Spicetify::new()?;
+        connector.theme = spicetify.theme;
+        connector.color_scheme = spicetify.color_scheme;
+        if spicetify.palette_scheme.unwrap_or(false) {
+            connector.palette = config.palette.clone();
         }
-        connectors.push(ConnectorConfig::Pprefox(connector));
+        connector.run_apply = spicetify.apply.unwrap_or(true);
+        connector.verify()?;
+        connectors.push(ConnectorConfig::Spicetify(connector));
     }
-    #[cfg(windows)]
-    if let Some(windows) = config.windows {
-        let mut available_cursors = HashMap::new();
-        for system_cursor in Windows::get_system_cursor_schemes().unwrap_or_default() {
-            available_cursors.insert(
-                system_cursor.clone(),
-                CursorScheme::SystemScheme(system_cursor),
-            );
+    for obsidian in config.obsidian.unwrap_or_default() {
+        let mut connector = Obsidian::new()?;
+        if let Some(vaults) = obsidian.vaults {
+            connector.vaults = vaults.into_iter().map(PathBuf::from).collect();
+        }
+        connector.css_theme = obsidian.css_theme;
+        connector.dark = obsidian
+            .dark
+            .or(config.palette.as_ref().map(|palette| !palette.is_light()));
+        if obsidian.accent_snippet.unwrap_or(false) {
+            connector.palette = config.palette.clone();
+        }
+        connector.verify()?;
+        connectors.push(ConnectorConfig::Obsidian(connector));
+    }
+    for cli in config.cli.unwrap_or_default() {
+        let mut connector = Cli::new()?;
+        connector.bat_theme = cli.bat_theme;
+        connector.delta_syntax_theme = cli.delta_syntax_theme;
+        connector.btop_theme = cli.btop_theme;
+        if cli.starship_palette.unwrap_or(false) {
+            connector.starship_palette = config.palette.clone();
         }
-        for user_cursor in Windows::get_user_cursor_schemes().unwrap_or_default() {
-            available_cursors.insert(user_cursor.clone(), CursorScheme::UserScheme(user_cursor));
+        if cli.btop_palette.unwrap_or(false) {
+            connector.btop_palette = config.palette.clone();
+        }
+        if cli.fzf_palette.unwrap_or(false) {
+            connector.fzf_palette = config.palette.clone();
+        }
+        connector.verify()?;
+        connectors.push(ConnectorConfig::Cli(connector));
+    }
+    for template in config.template.unwrap_or_default() {
+        let mut connector = Template::new()?;
+        connector.template = PathBuf::from(template.template);
+        connector.destination = PathBuf::from(template.destination);
+        connector.context = template::context(&config.palette, &config.name, &config.author);
+        connector.post_command = template.post_command;
+        connector.verify()?;
+        connectors.push(ConnectorConfig::Template(connector));
+    }
+    for http in config.http.unwrap_or_default() {
+        let mut connector = Http::new()?;
+        if let Some(method) = http.method {
+            connector.method = Method::from_str(&method.to_uppercase())?;
         }
-        for windows in windows {
-            let cursor_scheme = match windows.cursor_scheme {
-                None => None,
-                Some(cursor_scheme) => match available_cursors.get(&cursor_scheme) {
-                    None => todo!(),
-                    Some(scheme_with_type) => Some(scheme_with_type.clone()),
-                },
+        connector.url = http.url;
+        connector.headers = http.headers.unwrap_or_default().into_iter().collect();
+        connector.body = http.body;
+        connector.expected_status = http.expected_status;
+        connector.context = template::context(&config.palette, &config.name, &config.author);
+        if let Some(lookup) = http.lookup {
+            let name = lookup.name;
+            let lookup = Lookup {
+                url: lookup.url,
+                pointer: lookup.pointer.unwrap_or_default(),
+                id_field: lookup.id_field,
+                name_field: lookup.name_field.unwrap_or("name".to_string()),
             };
-            let mut connector = Windows::new()?;
-            connector.cursor_scheme = cursor_scheme;
-            connectors.push(ConnectorConfig::Windows(connector));
+            connector.resolve_id(&lookup, &name).await?;
         }
+        connector.verify()?;
+        connectors.push(ConnectorConfig::Http(connector));
+    }
+    for chromium in config.chromium.unwrap_or_default() {
+        let mut connector = Chromium::new()?;
+        connector.profiles =
+            Chromium::select(connector.profiles, &chromium.browser, &chromium.profiles)?;
+        connector.color_scheme = match chromium.color_scheme {
+            Some(color_scheme) => Some(ColorScheme::from_str(&color_scheme)?),
+            None => config.palette.as_ref().map(|palette| {
+                if palette.is_light() {
+                    ColorScheme::Light
+                } else {
+                    ColorScheme::Dark
+                }
+            }),
+        };
+        connector.user_color = chromium.user_color;
+        if chromium.theme_extension.unwrap_or(false) {
+            connector.theme_palette = config.palette.clone();
+        }
+        connector.verify()?;
+        connectors.push(ConnectorConfig::Chromium(connector));
     }
     #[cfg(windows)]
-    match config.wpeng {
-        None => (),
-        Some(wallpapers) => {
+    for connector in windows_connectors(
+        config.windows,
+        &config.cursor_scheme,
+        &config.fonts.as_ref().and_then(|fonts| fonts.ui.clone()),
+        &config.palette,
+        || Box::new(WindowsRegistry),
+    )? {
+        connectors.push(ConnectorConfig::Windows(connector));
+    }
+    #[cfg(windows)]
+    if let Some(wallpapers) = config.wpeng {
+        let mut connector = Wpeng::new()?;
+        // Only look at the monitors if a section picks one by more than its number
+        let layout = if wallpapers.iter().any(|wpeng| wpeng.monitor.is_some()) {
+            wpeng_monitor::current_layout()?
+        } else {
+            vec![]
+        };
+        for wpeng in wallpapers {
             // default desktop_id to 0
-            let wallpapers = wallpapers
-                .into_iter()
-                .map(|wpeng| (wpeng.desktop_id.unwrap_or(0), wpeng.name))
-                .collect::<HashMap<u8, Option<String>>>();
-            let mut connector = Wpeng::new()?;
-            connector.wallpapers = wallpapers;
-            connectors.push(ConnectorConfig::Wpeng(connector));
+            let desktop_id = match wpeng.monitor {
+                Some(monitor) => {
+                    wpeng_monitor::resolve(&layout, &MonitorTarget::from_str(&monitor)?)?
+                }
+                None => wpeng.desktop_id.unwrap_or(0),
+            };
+            match wpeng.playlist {
+                Some(playlist) => {
+                    connector.playlists.insert(desktop_id, playlist);
+                }
+                None => {
+                    let wallpaper = match (wpeng.workshop_id, wpeng.name) {
+                        (Some(workshop_id), _) => {
+                            Some(WallpaperId::Workshop(workshop_id.to_string()))
+                        }
+                        (None, Some(name)) => Some(WallpaperId::Name(name)),
+                        (None, None) => None,
+                    };
+                    connector.wallpapers.insert(desktop_id, wallpaper);
+                }
+            }
+            if let Some(properties) = wpeng.properties {
+                connector.properties.insert(
+                    desktop_id,
+                    wpeng_control::properties(&properties, config.palette.as_ref()),
+                );
+            }
+            if let Some(playback) = wpeng.playback {
+                connector.playback = Some(Playback::from_str(&playback)?);
+            }
+            if let Some(mute) = wpeng.mute {
+                connector.mute = Some(mute);
+            }
         }
+        connector.verify()?;
+        connectors.push(ConnectorConfig::Wpeng(connector));
     }
     for ledfx in config.ledfx.unwrap_or_default() {
         let mut connector = Ledfx::new()?;
@@ -121,6 +584,166 @@ pub async fn main() -> Result<(), Box<dyn std::error::Error + 'static>> {
         // Otherwise, None (default) to reset
         connectors.push(ConnectorConfig::Ledfx(connector));
     }
+    for vscode in config.vscode.unwrap_or_default() {
+        let mut connector = Vscode::new()?;
+        if let Some(flavor) = vscode.flavor {
+            connector.flavor = Flavor::from_str(&flavor)?;
+        }
+        connector.profile = vscode.profile;
+        connector.color_theme = vscode.color_theme;
+        connector.icon_theme = vscode.icon_theme;
+        connector.product_icon_theme = vscode.product_icon_theme;
+        connector.font_family = vscode.font_family;
+        connector.verify()?;
+        connectors.push(ConnectorConfig::Vscode(connector));
+    }
+    #[cfg(unix)]
+    for gtk in config.gtk.unwrap_or_default() {
+        let mut connector = Gtk::new()?;
+        connector.theme = gtk.theme;
+        connector.prefer_dark = gtk
+            .prefer_dark
+            .or(config.palette.as_ref().map(|palette| !palette.is_light()));
+        if gtk.libadwaita.unwrap_or(false) {
+            connector.libadwaita_palette = config.palette.clone();
+        }
+        connector.verify()?;
+        connectors.push(ConnectorConfig::Gtk(connector));
+    }
+    #[cfg(unix)]
+    for qt in config.qt.unwrap_or_default() {
+        let mut connector = Qt::new()?;
+        connector.style = qt.style;
+        connector.icon_theme = qt.icon_theme.or_else(|| config.icon_theme.clone());
+        connector.color_scheme = qt.color_scheme;
+        connector.kvantum_theme = qt.kvantum_theme;
+        if qt.kvantum_palette.unwrap_or(false) {
+            connector.kvantum_palette = config.palette.clone();
+        }
+        connector.verify()?;
+        connectors.push(ConnectorConfig::Qt(connector));
+    }
+    // Same as on Windows, the top-level cursor settings apply without an `[[xcursor]]` section
+    #[cfg(unix)]
+    let xcursors = match config.xcursor {
+        None if config.cursor_scheme.is_some() || config.cursor_size.is_some() => {
+            vec![univeme::toml::Xcursor::default()]
+        }
+        xcursors => xcursors.unwrap_or_default(),
+    };
+    // `[[xresources]]` takes over `Xcursor.*` and defaults to the cursor set here
+    #[cfg(unix)]
+    let has_xresources = config.xresources.as_ref().is_some_and(|x| !x.is_empty());
+    #[cfg(unix)]
+    let (mut xcursor_theme, mut xcursor_size) = (None, None);
+    #[cfg(unix)]
+    for xcursor in xcursors {
+        let mut connector = Xcursor::new()?;
+        // A shared preset can name a Windows-only scheme, and the Windows reset keyword has no
+        // Linux equivalent, so the top-level name is only used when it is an installed theme
+        connector.theme = xcursor.theme.or_else(|| {
+            let scheme = config
+                .cursor_scheme
+                .clone()
+                .filter(|scheme| scheme != WINDOWS_DEFAULT_SCHEME)?;
+            if Xcursor::get_installed_themes().contains(&scheme) {
+                Some(scheme)
+            } else {
+                eprintln!("xcursor: {} is not an installed cursor theme, leaving the cursor theme as it is", scheme);
+                None
+            }
+        });
+        connector.size = xcursor.size.or(config.cursor_size);
+        connector.xresources = !has_xresources;
+        connector.verify()?;
+        xcursor_theme = connector.theme.clone();
+        xcursor_size = connector.size;
+        connectors.push(ConnectorConfig::Xcursor(connector));
+    }
+    #[cfg(unix)]
+    for tmux in config.tmux.unwrap_or_default() {
+        let mut connector = Tmux::new()?;
+        connector.theme = tmux.theme;
+        if tmux.palette_theme.unwrap_or(false) {
+            connector.palette = config.palette.clone();
+        }
+        connector.verify()?;
+        connectors.push(ConnectorConfig::Tmux(connector));
+    }
+    #[cfg(unix)]
+    for zellij in config.zellij.unwrap_or_default() {
+        let mut connector = Zellij::new()?;
+        connector.theme = zellij.theme;
+        if zellij.palette_theme.unwrap_or(false) {
+            connector.palette = config.palette.clone();
+        }
+        connector.verify()?;
+        connectors.push(ConnectorConfig::Zellij(connector));
+    }
+    #[cfg(unix)]
+    for notifications in config.notifications.unwrap_or_default() {
+        let mut connector = Notifications::new()?;
+        if notifications.dunst.unwrap_or(false) {
+            connector.dunst = config.palette.clone();
+        }
+        if notifications.mako.unwrap_or(false) {
+            connector.mako = config.palette.clone();
+        }
+        connector.verify()?;
+        connectors.push(ConnectorConfig::Notifications(connector));
+    }
+    #[cfg(unix)]
+    for launcher in config.launcher.unwrap_or_default() {
+        let mut connector = Launcher::new()?;
+        if launcher.rofi.unwrap_or(false) {
+            connector.rofi = config.palette.clone();
+        }
+        if launcher.fuzzel.unwrap_or(false) {
+            connector.fuzzel = config.palette.clone();
+        }
+        if launcher.wofi.unwrap_or(false) {
+            connector.wofi = config.palette.clone();
+        }
+        connector.verify()?;
+        connectors.push(ConnectorConfig::Launcher(connector));
+    }
+    #[cfg(unix)]
+    for bar in config.bar.unwrap_or_default() {
+        let mut connector = Bar::new()?;
+        if bar.waybar.unwrap_or(false) {
+            connector.waybar = config.palette.clone();
+        }
+        if bar.polybar.unwrap_or(false) {
+            connector.polybar = config.palette.clone();
+        }
+        if bar.eww.unwrap_or(false) {
+            connector.eww = config.palette.clone();
+        }
+        connector.verify()?;
+        connectors.push(ConnectorConfig::Bar(connector));
+    }
+    #[cfg(unix)]
+    for xresources in config.xresources.unwrap_or_default() {
+        let mut connector = Xresources::new()?;
+        if xresources.palette_colors.unwrap_or(false) {
+            connector.palette = config.palette.clone();
+        }
+        connector.cursor_theme = xresources.cursor_theme.or(xcursor_theme.clone());
+        connector.cursor_size = xcursor_size;
+        connector.verify()?;
+        connectors.push(ConnectorConfig::Xresources(connector));
+    }
+    #[cfg(unix)]
+    if config.icon_theme.is_some() || config.fonts.is_some() {
+        let fonts = config.fonts.clone().unwrap_or_default();
+        let mut connector = Desktop::new()?;
+        connector.icon_theme = config.icon_theme.clone();
+        connector.ui_font = fonts.ui;
+        connector.monospace_font = fonts.monospace;
+        connector.size = fonts.size;
+        connector.verify()?;
+        connectors.push(ConnectorConfig::Desktop(connector));
+    }
     for connector in connectors {
         match connector {
             #[allow(unused_variables)]
@@ -129,6 +752,47 @@ pub async fn main() -> Result<(), Box<dyn std::error::Error + 'static>> {
                 #[cfg(windows)]
                 pprefox.apply().await?;
             }
+            ConnectorConfig::Firefox(firefox) => {
+                firefox.apply().await?;
+                if firefox.needs_restart() {
+                    eprintln!("firefox: Firefox is running, restart it to see the changes");
+                }
+            }
+            ConnectorConfig::Thunderbird(thunderbird) => {
+                thunderbird.apply().await?;
+                if thunderbird.needs_restart() {
+                    eprintln!("thunderbird: Thunderbird is running, restart it to see the changes");
+                }
+            }
+            ConnectorConfig::Discord(discord) => {
+                discord.apply().await?;
+            }
+            ConnectorConfig::Spicetify(spicetify) => {
+                spicetify.apply().await?;
+            }
+            ConnectorConfig::Obsidian(obsidian) => {
+                obsidian.apply().await?;
+            }
+            ConnectorConfig::Cli(cli) => {
+                cli.apply().await?;
+            }
+            ConnectorConfig::Template(template) => {
+                template.apply().await?;
+            }
+            ConnectorConfig::Http(http) => {
+                http.apply().await?;
+            }
+            ConnectorConfig::Chromium(chromium) => {
+                chromium.apply().await?;
+                for profile in chromium.profiles_without_theme()? {
+                    eprintln!(
+                        "chromium: {} ({}): load the theme once from the extensions page with \"Load unpacked\": {}",
+                        profile.name,
+                        profile.browser,
+                        Chromium::theme_dir()?.to_string_lossy()
+                    );
+                }
+            }
             #[cfg(windows)]
             ConnectorConfig::Windows(windows) => {
                 windows.apply().await?;
@@ -140,6 +804,49 @@ pub async fn main() -> Result<(), Box<dyn std::error::Error + 'static>> {
             ConnectorConfig::Ledfx(ledfx) => {
                 ledfx.apply().await?;
             }
+            ConnectorConfig::Vscode(vscode) => {
+                vscode.apply().await?;
+            }
+            #[cfg(unix)]
+            ConnectorConfig::Gtk(gtk) => {
+                gtk.apply().await?;
+            }
+            #[cfg(unix)]
+            ConnectorConfig::Qt(qt) => {
+                qt.apply().await?;
+            }
+            #[cfg(unix)]
+            ConnectorConfig::Xcursor(xcursor) => {
+                xcursor.apply().await?;
+            }
+            #[cfg(unix)]
+            ConnectorConfig::Tmux(tmux) => {
+                tmux.apply().await?;
+            }
+            #[cfg(unix)]
+            ConnectorConfig::Zellij(zellij) => {
+                zellij.apply().await?;
+            }
+            #[cfg(unix)]
+            ConnectorConfig::Notifications(notifications) => {
+                notifications.apply().await?;
+            }
+            #[cfg(unix)]
+            ConnectorConfig::Launcher(launcher) => {
+                launcher.apply().await?;
+            }
+            #[cfg(unix)]
+            ConnectorConfig::Bar(bar) => {
+                bar.apply().await?;
+            }
+            #[cfg(unix)]
+            ConnectorConfig::Xresources(xresources) => {
+                xresources.apply().await?;
+            }
+            #[cfg(unix)]
+            ConnectorConfig::Desktop(desktop) => {
+                desktop.apply().await?;
+            }
         }
     }
     Ok(())
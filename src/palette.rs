@@ -0,0 +1,158 @@
+use serde::Deserialize;
+use std::{fmt, str::FromStr};
+
+custom_error::custom_error! {pub ColorError
+    InvalidColor{color: String} = "invalid color (expected #rrggbb): {color}"
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(try_from = "String")]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl FromStr for Color {
+    type Err = ColorError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ColorError::InvalidColor {
+            color: s.to_string(),
+        };
+        let hex = s.trim().trim_start_matches('#');
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+        // Allow the short #rgb form too
+        let hex = match hex.len() {
+            3 => hex.chars().flat_map(|c| [c, c]).collect::<String>(),
+            6 => hex.to_string(),
+            _ => return Err(invalid()),
+        };
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid());
+        Ok(Color {
+            r: channel(0)?,
+            g: channel(2)?,
+            b: channel(4)?,
+        })
+    }
+}
+
+impl TryFrom<String> for Color {
+    type Error = ColorError;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Color::from_str(&value)
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+impl Color {
+    pub const BLACK: Color = Color { r: 0, g: 0, b: 0 };
+    pub const WHITE: Color = Color {
+        r: 255,
+        g: 255,
+        b: 255,
+    };
+
+    /// `rrggbb` without the leading `#`, for formats that do not want it.
+    pub fn hex_bare(&self) -> String {
+        format!("{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+    /// Blends towards `other`. `amount` of 0 is this color, 1 is `other`.
+    pub fn mix(&self, other: &Color, amount: f32) -> Color {
+        let amount = amount.clamp(0.0, 1.0);
+        let channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * amount).round() as u8;
+        Color {
+            r: channel(self.r, other.r),
+            g: channel(self.g, other.g),
+            b: channel(self.b, other.b),
+        }
+    }
+    /// Relative luminance from 0 (black) to 1 (white).
+    pub fn luminance(&self) -> f32 {
+        let linear = |c: u8| {
+            let c = c as f32 / 255.0;
+            if c <= 0.03928 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        0.2126 * linear(self.r) + 0.7152 * linear(self.g) + 0.0722 * linear(self.b)
+    }
+    /// Black or white, whichever is readable on top of this color.
+    pub fn contrast(&self) -> Color {
+        if self.luminance() > 0.4 {
+            Color::BLACK
+        } else {
+            Color::WHITE
+        }
+    }
+}
+
+/// The colors a preset is built around. Connectors that generate theme files take their
+/// colors from here.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Palette {
+    // Whether this is a light theme. Guessed from `background` when left out
+    pub light_mode: Option<bool>,
+    pub background: Color,
+    pub foreground: Color,
+    pub accent: Color,
+    // Panels, headerbars, sidebars. Derived from `background` when left out
+    pub surface: Option<Color>,
+    // The 16 terminal colors, `color0` to `color15`
+    pub colors: Option<Vec<Color>>,
+}
+
+impl Palette {
+    pub fn is_light(&self) -> bool {
+        self.light_mode
+            .unwrap_or_else(|| self.background.luminance() > 0.5)
+    }
+    pub fn surface(&self) -> Color {
+        self.surface
+            .unwrap_or_else(|| self.background.mix(&self.foreground, 0.08))
+    }
+    /// Text color to use on top of `accent`.
+    pub fn accent_foreground(&self) -> Color {
+        self.accent.contrast()
+    }
+    /// Every color the palette has, by the names `role` knows them by.
+    pub fn named_colors(&self) -> Vec<(String, Color)> {
+        let mut names = [
+            "background",
+            "foreground",
+            "accent",
+            "accent_foreground",
+            "surface",
+        ]
+        .map(str::to_string)
+        .to_vec();
+        names.extend((0..16).map(|index| format!("color{}", index)));
+        names
+            .into_iter()
+            .filter_map(|name| Some((name.clone(), self.role(&name)?)))
+            .collect()
+    }
+    /// Looks up a color by the name presets use for it: `background`, `foreground`, `accent`,
+    /// `surface`, `accent_foreground` or `color0` to `color15`.
+    pub fn role(&self, name: &str) -> Option<Color> {
+        match name {
+            "background" => Some(self.background),
+            "foreground" => Some(self.foreground),
+            "accent" => Some(self.accent),
+            "surface" => Some(self.surface()),
+            "accent_foreground" => Some(self.accent_foreground()),
+            _ => {
+                let index = name.strip_prefix("color")?.parse::<usize>().ok()?;
+                self.colors.as_ref()?.get(index).copied()
+            }
+        }
+    }
+}
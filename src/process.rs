@@ -0,0 +1,40 @@
+use std::process::Command;
+
+/// Runs `program` and reports whether it exited successfully. A program that is not
+/// installed counts as a failure instead of an error, since reloading is best effort.
+pub fn run(program: &str, args: &[&str]) -> bool {
+    Command::new(program)
+        .args(args)
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+/// Runs `program` and returns what it printed, or `None` if it failed or is not installed.
+pub fn output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Whether a process with exactly this name (without `.exe`) is running.
+#[cfg(unix)]
+pub fn is_running(name: &str) -> bool {
+    run("pgrep", &["-x", name])
+}
+
+#[cfg(windows)]
+pub fn is_running(name: &str) -> bool {
+    let image = format!("{}.exe", name);
+    Command::new("tasklist")
+        .args(["/NH", "/FI", &format!("IMAGENAME eq {}", image)])
+        .output()
+        .map(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .to_lowercase()
+                .contains(&image.to_lowercase())
+        })
+        .unwrap_or(false)
+}
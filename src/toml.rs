@@ -1,34 +1,112 @@
+use std::collections::BTreeMap;
+
 use serde::Deserialize;
 
+use crate::palette::{Color, Palette};
+
 #[derive(Deserialize, Debug)]
 pub struct Config {
     pub name: Option<String>,
     pub author: Option<String>,
+    pub palette: Option<Palette>,
+    // Applied to `[[windows]]` and `[[xcursor]]` when they do not set their own
+    pub cursor_scheme: Option<String>,
+    // Linux only, Windows cursor schemes have fixed sizes
+    pub cursor_size: Option<u32>,
+    // Linux only, applied to `[[qt]]` when it does not set its own
+    pub icon_theme: Option<String>,
+    pub fonts: Option<Fonts>,
     pub pprefox: Option<Vec<Pprefox>>,
     pub windows: Option<Vec<Windows>>,
     pub wpeng: Option<Vec<Wpeng>>,
     pub ledfx: Option<Vec<Ledfx>>,
+    pub vscode: Option<Vec<Vscode>>,
+    pub gtk: Option<Vec<Gtk>>,
+    pub qt: Option<Vec<Qt>>,
+    pub xcursor: Option<Vec<Xcursor>>,
+    pub firefox: Option<Vec<Firefox>>,
+    pub chromium: Option<Vec<Chromium>>,
+    pub thunderbird: Option<Vec<Thunderbird>>,
+    pub discord: Option<Vec<Discord>>,
+    pub spicetify: Option<Vec<Spicetify>>,
+    pub obsidian: Option<Vec<Obsidian>>,
+    pub cli: Option<Vec<Cli>>,
+    pub tmux: Option<Vec<Tmux>>,
+    pub zellij: Option<Vec<Zellij>>,
+    pub notifications: Option<Vec<Notifications>>,
+    pub launcher: Option<Vec<Launcher>>,
+    pub bar: Option<Vec<Bar>>,
+    pub xresources: Option<Vec<Xresources>>,
+    pub template: Option<Vec<Template>>,
+    pub http: Option<Vec<Http>>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct Fonts {
+    // Font family for the desktop's UI. On Windows it replaces Segoe UI
+    pub ui: Option<String>,
+    // Linux only, Windows has no system-wide monospace font
+    pub monospace: Option<String>,
+    // In points, Linux only. Defaults to 11
+    pub size: Option<u32>,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct Pprefox {
     pub comment: Option<String>,
+    // Only this host when set, otherwise every one that can be found
     pub endpoint: Option<String>,
+    // Names from the instances file, or `127.0.0.1:<port>` for scanned ones. Defaults to all
+    pub profiles: Option<Vec<String>>,
+    // First and last port to scan. Defaults to 8080 to 8099
+    pub ports: Option<[u16; 2]>,
     // Since we do not have a real default in Firefox, there will be no `None` for resetting
     pub theme_name: String,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Default)]
 pub struct Windows {
     pub comment: Option<String>,
+    // Path to a .theme file
+    pub theme_path: Option<String>,
+    // Defaults to the palette's `light_mode`
+    pub light_mode: Option<bool>,
+    pub enable_color_prevalence: Option<bool>,
+    pub accent_color: Option<Color>,
+    pub transparency: Option<bool>,
+    // `WindowsDefault` resets to the built-in cursors
     pub cursor_scheme: Option<String>,
+    // Folder or .zip of a cursor pack to install. Its scheme is selected unless `cursor_scheme` is set
+    pub cursor_pack: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct Wpeng {
     pub comment: Option<String>,
     pub name: Option<String>,
+    // The number at the end of the wallpaper's Steam Workshop URL. Used instead of `name` when set
+    pub workshop_id: Option<u64>,
     pub desktop_id: Option<u8>,
+    // `primary`, `left`, `right`, a resolution like `2560x1440` or a monitor name. Used instead of `desktop_id` when set
+    pub monitor: Option<String>,
+    // Loaded instead of a wallpaper when set
+    pub playlist: Option<String>,
+    // Wallpaper user properties, by property key
+    pub properties: Option<BTreeMap<String, PropertyValue>>,
+    // `play`, `pause` or `stop`. Applies to every monitor
+    pub playback: Option<String>,
+    // Applies to every monitor
+    pub mute: Option<bool>,
+}
+
+/// A wallpaper user property. Text that names a palette color (`accent`, `color4`, ...) or is a
+/// `#rrggbb` color becomes a color.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum PropertyValue {
+    Bool(bool),
+    Number(f64),
+    Text(String),
 }
 
 #[derive(Deserialize, Debug)]
@@ -38,3 +116,233 @@ pub struct Ledfx {
     // `None` to reset
     pub scene_name: Option<String>,
 }
+
+#[derive(Deserialize, Debug)]
+pub struct Vscode {
+    pub comment: Option<String>,
+    // `code`, `code-oss`, `vscodium` or `insiders`. Defaults to `code`
+    pub flavor: Option<String>,
+    pub profile: Option<String>,
+    pub color_theme: Option<String>,
+    pub icon_theme: Option<String>,
+    pub product_icon_theme: Option<String>,
+    pub font_family: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Gtk {
+    pub comment: Option<String>,
+    pub theme: Option<String>,
+    // Defaults to the opposite of the palette's `light_mode`
+    pub prefer_dark: Option<bool>,
+    // Whether to override libadwaita's colors with the palette. Defaults to false
+    pub libadwaita: Option<bool>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Qt {
+    pub comment: Option<String>,
+    // Defaults to `kvantum` when a Kvantum theme is set
+    pub style: Option<String>,
+    pub icon_theme: Option<String>,
+    pub color_scheme: Option<String>,
+    pub kvantum_theme: Option<String>,
+    // Whether to override the Kvantum theme's colors with the palette. Defaults to false
+    pub kvantum_palette: Option<bool>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct Xcursor {
+    pub comment: Option<String>,
+    pub theme: Option<String>,
+    pub size: Option<u32>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Firefox {
+    pub comment: Option<String>,
+    // Profile names from profiles.ini. Defaults to all of them
+    pub profiles: Option<Vec<String>>,
+    // Color the browser UI and about: pages with the palette
+    pub user_chrome: Option<bool>,
+    // Defaults to the palette's `light_mode`
+    pub dark: Option<bool>,
+    // Add-on ID of an installed theme. Only switched while Firefox is closed
+    pub theme_id: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Chromium {
+    pub comment: Option<String>,
+    // `chrome`, `chromium`, `brave` or `edge`. Defaults to all of them
+    pub browser: Option<String>,
+    // Names from the profile menu or profile folder names. Defaults to all of them
+    pub profiles: Option<Vec<String>>,
+    // `system`, `light` or `dark`. Defaults to the palette's `light_mode`
+    pub color_scheme: Option<String>,
+    pub user_color: Option<Color>,
+    // Generate a theme extension from the palette
+    pub theme_extension: Option<bool>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Thunderbird {
+    pub comment: Option<String>,
+    // Profile names from profiles.ini. Defaults to all of them
+    pub profiles: Option<Vec<String>>,
+    // Color the folder pane, message list and toolbars with the palette
+    pub user_chrome: Option<bool>,
+    // Defaults to the palette's `light_mode`
+    pub dark: Option<bool>,
+    // Add-on ID of an installed theme. Only switched while Thunderbird is closed
+    pub theme_id: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Discord {
+    pub comment: Option<String>,
+    // `vencord`, `vesktop` or `betterdiscord`. Defaults to every one that is installed
+    pub client_mods: Option<Vec<String>>,
+    // A theme .css file to install. Wins over `palette_theme`
+    pub theme_file: Option<String>,
+    // Generate a theme from the palette
+    pub palette_theme: Option<bool>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Spicetify {
+    pub comment: Option<String>,
+    // Folder name in spicetify's Themes
+    pub theme: Option<String>,
+    // Section of the theme's color.ini
+    pub color_scheme: Option<String>,
+    // Add a `univeme` color scheme from the palette to the theme and select it. Wins over `color_scheme`
+    pub palette_scheme: Option<bool>,
+    // Run `spicetify apply` afterwards. Defaults to true
+    pub apply: Option<bool>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Obsidian {
+    pub comment: Option<String>,
+    // Vault folders. Defaults to every vault Obsidian knows about
+    pub vaults: Option<Vec<String>>,
+    // Community theme installed in the vaults, "" for the default theme
+    pub css_theme: Option<String>,
+    // Defaults to the palette's `light_mode`
+    pub dark: Option<bool>,
+    // Set the accent color and turn on a `univeme` CSS snippet made from the palette
+    pub accent_snippet: Option<bool>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Cli {
+    pub comment: Option<String>,
+    // Name from `bat --list-themes`
+    pub bat_theme: Option<String>,
+    // Same names as `bat_theme`
+    pub delta_syntax_theme: Option<String>,
+    // Add a `univeme` palette to starship.toml and select it
+    pub starship_palette: Option<bool>,
+    pub btop_theme: Option<String>,
+    // Generate a `univeme` btop theme and select it. Wins over `btop_theme`
+    pub btop_palette: Option<bool>,
+    // Write `FZF_DEFAULT_OPTS` colors to an env file
+    pub fzf_palette: Option<bool>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Tmux {
+    pub comment: Option<String>,
+    // File name in ~/.config/tmux/themes, without `.conf`
+    pub theme: Option<String>,
+    // Generate the theme from the palette. Wins over `theme`
+    pub palette_theme: Option<bool>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Zellij {
+    pub comment: Option<String>,
+    pub theme: Option<String>,
+    // Generate a `univeme` theme from the palette and select it. Wins over `theme`
+    pub palette_theme: Option<bool>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Notifications {
+    pub comment: Option<String>,
+    // Write the palette into dunstrc
+    pub dunst: Option<bool>,
+    // Write the palette into mako's config
+    pub mako: Option<bool>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Launcher {
+    pub comment: Option<String>,
+    // Generate a rofi theme from the palette and select it
+    pub rofi: Option<bool>,
+    // Write the palette into fuzzel.ini
+    pub fuzzel: Option<bool>,
+    // Add the palette to wofi's style.css
+    pub wofi: Option<bool>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Bar {
+    pub comment: Option<String>,
+    // Write the palette to ~/.config/waybar/univeme-colors.css
+    pub waybar: Option<bool>,
+    // Write the palette to ~/.config/polybar/univeme-colors.ini
+    pub polybar: Option<bool>,
+    // Write the palette to ~/.config/eww/univeme-colors.scss
+    pub eww: Option<bool>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Xresources {
+    pub comment: Option<String>,
+    // Write the palette's foreground, background and 16 colors
+    pub palette_colors: Option<bool>,
+    // XCursor theme for `Xcursor.theme`. Defaults to the `[[xcursor]]` or top-level cursor
+    pub cursor_theme: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Template {
+    pub comment: Option<String>,
+    // Jinja template, rendered with the palette, name, author and light/dark mode
+    pub template: String,
+    pub destination: String,
+    // Run through the shell after writing, to reload the app
+    pub post_command: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Http {
+    pub comment: Option<String>,
+    // Defaults to `POST`
+    pub method: Option<String>,
+    // The URL, header values and body are Jinja templates, like `[[template]]`
+    pub url: String,
+    pub headers: Option<BTreeMap<String, String>>,
+    // Must render to valid JSON
+    pub body: Option<String>,
+    // Defaults to any 2xx
+    pub expected_status: Option<u16>,
+    // Finds an ID by name, available to the templates as `id`
+    pub lookup: Option<HttpLookup>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct HttpLookup {
+    // Fetched with GET, should return a list or an object of entries
+    pub url: String,
+    // JSON pointer to the entries, like `/scenes`. Defaults to the whole response
+    pub pointer: Option<String>,
+    // Defaults to `name`
+    pub name_field: Option<String>,
+    // Defaults to the entries' keys, when they are an object
+    pub id_field: Option<String>,
+    pub name: String,
+}
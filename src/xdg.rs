@@ -0,0 +1,57 @@
+use std::{env, path::PathBuf};
+
+/// `$XDG_CONFIG_HOME`, or `~/.config`. Command line tools use this on every platform,
+/// including macOS and Windows, where `dirs::config_dir` points somewhere else.
+pub fn config_home() -> Option<PathBuf> {
+    match env::var_os("XDG_CONFIG_HOME") {
+        Some(config) if !config.is_empty() => Some(PathBuf::from(config)),
+        _ => dirs::home_dir().map(|home| home.join(".config")),
+    }
+}
+
+/// `$XDG_DATA_HOME` followed by `$XDG_DATA_DIRS`, the places themes get installed to.
+pub fn data_dirs() -> Vec<PathBuf> {
+    let mut data_dirs = vec![];
+    if let Some(data) = dirs::data_dir() {
+        data_dirs.push(data);
+    }
+    match env::var("XDG_DATA_DIRS") {
+        Ok(system) if !system.is_empty() => {
+            data_dirs.extend(env::split_paths(&system));
+        }
+        _ => {
+            data_dirs.push(PathBuf::from("/usr/local/share"));
+            data_dirs.push(PathBuf::from("/usr/share"));
+        }
+    }
+    data_dirs
+}
+
+/// Icon and cursor theme folders, in the order they are searched.
+pub fn icon_dirs() -> Vec<PathBuf> {
+    let mut icon_dirs = vec![];
+    if let Some(home) = dirs::home_dir() {
+        icon_dirs.push(home.join(".icons"));
+    }
+    icon_dirs.extend(data_dirs().into_iter().map(|data| data.join("icons")));
+    icon_dirs.push(PathBuf::from("/usr/share/pixmaps"));
+    icon_dirs
+}
+
+/// Names of the icon themes found in `icon_dirs`. Cursor-only themes are included.
+pub fn icon_themes() -> Vec<String> {
+    let mut themes = vec![];
+    for icon_dir in icon_dirs() {
+        let Ok(entries) = std::fs::read_dir(icon_dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            if entry.path().join("index.theme").is_file() {
+                themes.push(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+    }
+    themes.sort();
+    themes.dedup();
+    themes
+}